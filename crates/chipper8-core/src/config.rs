@@ -1,4 +1,4 @@
-use std::ops::Range;
+use core::ops::Range;
 
 // todo: make these configurable, but keep machine _state_ separate from machine _config_
 pub const MEMORY_SIZE: usize = 4096;
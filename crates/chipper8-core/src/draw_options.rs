@@ -1,5 +1,5 @@
-use std::cmp::min;
-use std::ops::BitXorAssign;
+use core::cmp::min;
+use core::ops::BitXorAssign;
 
 pub struct DrawOptions<'a> {
     pos: [usize; 2],
@@ -34,7 +34,7 @@ impl<'a> DrawOptions<'a> {
             let mut byte = bytes[j - y];
             for i in x..min(x + 8, display_width) {
                 let target = &mut self.target[i + j * display_width];
-                let last = target.clone();
+                let last = *target;
                 target.bitxor_assign(if byte & 0b10000000 != 0 { 0xFF } else { 0 });
                 if last != 0 && *target == 0 {
                     pixel_off_flag = true;
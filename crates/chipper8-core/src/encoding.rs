@@ -0,0 +1,117 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serializer};
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// standard base64, with padding
+pub fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| bits | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(bits >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// decodes base64 (with or without padding), or `None` if the text isn't base64
+pub fn from_base64(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut bits, mut count) = (0u32, 0);
+    for c in text.bytes() {
+        let value = BASE64_ALPHABET.iter().position(|a| *a == c)?;
+        bits = bits << 6 | value as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2)
+        .map(|i| text.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect()
+}
+
+/// packs display pixels (any non-zero value is on) into bits, leftmost pixel first
+pub fn pack_pixels(pixels: &[u8]) -> Vec<u8> {
+    pixels.chunks(8)
+        .map(|chunk| chunk.iter().enumerate().fold(0, |byte, (i, pixel)| byte | ((*pixel != 0) as u8) << (7 - i)))
+        .collect()
+}
+
+/// unpacks `count` display pixels packed by `pack_pixels`, with pixels that are on set to 0xFF
+pub fn unpack_pixels(bits: &[u8], count: usize) -> Vec<u8> {
+    (0..count).map(|i| match bits.get(i / 8) {
+        Some(byte) if byte & 0x80 >> (i % 8) != 0 => 0xFF,
+        _ => 0,
+    }).collect()
+}
+
+// byte fields were written as arrays of numbers before they were encoded as strings
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EncodedBytes {
+    Array(Vec<u8>),
+    Text(String),
+}
+
+/// serializes bytes (e.g. memory) as a base64 string; arrays of numbers are read too
+#[cfg(feature = "serde")]
+pub mod base64_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> core::result::Result<Vec<u8>, D::Error> {
+        match EncodedBytes::deserialize(deserializer)? {
+            EncodedBytes::Array(bytes) => Ok(bytes),
+            EncodedBytes::Text(text) => from_base64(&text).ok_or_else(|| serde::de::Error::custom("invalid base64")),
+        }
+    }
+}
+
+/// serializes display pixels as a hex string of packed bits, 16 digits per row; arrays of
+/// numbers are read too
+#[cfg(feature = "serde")]
+pub mod hex_pixels {
+    use super::*;
+    use crate::config::DISPLAY_SIZE;
+
+    pub fn serialize<S: Serializer>(pixels: &[u8], serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex(&pack_pixels(pixels)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> core::result::Result<Vec<u8>, D::Error> {
+        match EncodedBytes::deserialize(deserializer)? {
+            EncodedBytes::Array(pixels) => Ok(pixels),
+            EncodedBytes::Text(text) => {
+                let bits = from_hex(&text).ok_or_else(|| serde::de::Error::custom("invalid hex string"))?;
+                Ok(unpack_pixels(&bits, DISPLAY_SIZE))
+            }
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use alloc::string::String;
+use core::fmt::{Display, Formatter};
+
+use crate::{Instruction, OpCode};
+
+#[derive(Debug)]
+pub enum Error {
+    IntSizeError(String, u32),
+    NoOpcodeError(Instruction),
+    InvalidOpCode(OpCode),
+    // todo: move this into a separate error enum inside the machine module
+    MachineExit,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::IntSizeError(kind, value) => write!(f, "integer too large for type: {} {}", kind, value),
+            Self::NoOpcodeError(instruction) => write!(f, "assembler error: no opcode for `{}`", instruction),
+            Self::InvalidOpCode(op_code) => write!(f, "invalid opcode: {}", op_code),
+            Self::MachineExit => write!(f, "normal machine exit"),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+pub type Result<T> = core::result::Result<T, Error>;
@@ -1,6 +1,6 @@
-use std::fmt::{Debug, Display, Formatter};
+use core::fmt::{Debug, Display, Formatter};
 
-use crate::machine::types::{Address, Byte, Nibble, Register};
+use crate::types::{Address, Byte, Nibble, Register};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Timer {
@@ -9,7 +9,7 @@ pub enum Timer {
 }
 
 impl Display for Timer {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Delay => write!(f, "VT"),
             Self::Sound => write!(f, "VS"),
@@ -24,7 +24,7 @@ pub enum Target {
 }
 
 impl Display for Target {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Register(vx) => write!(f, "{}", vx),
             Self::Timer(timer) => write!(f, "{}", timer),
@@ -40,7 +40,7 @@ pub enum Source {
 }
 
 impl Display for Source {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Byte(x) => write!(f, "{}", x),
             Self::Register(vx) => write!(f, "{}", vx),
@@ -66,7 +66,7 @@ pub enum BinaryOp {
 }
 
 impl Display for BinaryOp {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", match self {
             Self::Assign => "=",
             Self::Add => "+=",
@@ -99,7 +99,7 @@ pub enum IndexSource {
 }
 
 impl Display for IndexSource {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Value(x) => write!(f, "{}", x),
             Self::Register(vx) => write!(f, "{}", vx),
@@ -115,7 +115,7 @@ pub enum IndexOp {
 }
 
 impl Display for IndexOp {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", match self {
             Self::Assign => "=",
             Self::Add => "+=",
@@ -166,7 +166,7 @@ pub struct RegisterArgs {
 }
 
 impl Display for RegisterArgs {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.register)
     }
 }
@@ -178,7 +178,7 @@ pub struct JumpArgs {
 }
 
 impl Display for JumpArgs {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         if let Some(register) = &self.register {
             write!(f, "{} {}", self.address, register)
         } else {
@@ -194,7 +194,7 @@ pub enum Comparator {
 }
 
 impl Display for Comparator {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", match self {
             Self::Equal => "",
             Self::NotEqual => "!",
@@ -210,7 +210,7 @@ pub struct BranchArgs {
 }
 
 impl Display for BranchArgs {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{} {} {}", self.lhs, self.rhs, self.comparator)
     }
 }
@@ -222,7 +222,7 @@ pub struct InputBranchArgs {
 }
 
 impl Display for InputBranchArgs {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{} {}", self.key, self.comparator)
     }
 }
\ No newline at end of file
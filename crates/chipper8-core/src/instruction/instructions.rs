@@ -1,4 +1,4 @@
-use std::fmt::{Debug, Display, Formatter};
+use core::fmt::{Debug, Display, Formatter};
 
 use super::args::{BinaryOpArgs, BranchArgs, DrawArgs, IndexOpArgs, InputBranchArgs, JumpArgs, RegisterArgs};
 
@@ -42,7 +42,7 @@ pub enum Input {
 }
 
 impl Display for Instruction {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Exit => write!(f, "exit"),
             Self::Graphics(graphics) => write!(f, "graphics {}", graphics),
@@ -57,7 +57,7 @@ impl Display for Instruction {
 }
 
 impl Display for Graphics {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Clear => write!(f, "clear"),
             Self::Draw { args } => write!(f, "draw {} {} {}", args.x, args.y, args.height),
@@ -66,7 +66,7 @@ impl Display for Graphics {
 }
 
 impl Display for Flow {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Sys { args } => write!(f, "sys {}", args),
             Self::Jump { args } => write!(f, "jump {}", args),
@@ -78,7 +78,7 @@ impl Display for Flow {
 }
 
 impl Display for Memory {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Load { args } => write!(f, "load {}", args),
             Self::Save { args } => write!(f, "save {}", args),
@@ -87,7 +87,7 @@ impl Display for Memory {
 }
 
 impl Display for Input {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Await { args } => write!(f, "await {}", args),
             Self::Branch { args } => write!(f, "branch {}", args),
@@ -1,10 +1,10 @@
 pub use args::{BinaryOpArgs, DrawArgs, JumpArgs};
-pub use instruction::{Flow, Graphics, Input, Instruction, Memory};
+pub use instructions::{Flow, Graphics, Input, Instruction, Memory};
 pub use op_code::OpCode;
 
 pub mod args;
 mod op_code;
-mod instruction;
+mod instructions;
 
 #[cfg(test)]
 mod tests;
\ No newline at end of file
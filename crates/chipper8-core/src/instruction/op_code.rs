@@ -1,9 +1,11 @@
-use std::fmt::{Debug, Display, Formatter};
+use alloc::format;
+use alloc::string::String;
+use core::fmt::{Debug, Display, Formatter};
 
 use crate::{Error, Result};
-use crate::machine::instruction::{Flow, Graphics, Input, Memory};
-use crate::machine::instruction::args::{BinaryOp, BinaryOpArgs, BranchArgs, Comparator, DrawArgs, IndexOp, IndexOpArgs, IndexSource, InputBranchArgs, JumpArgs, RegisterArgs, Source, Target, Timer};
-use crate::machine::types::{Register, Word};
+use crate::instruction::{Flow, Graphics, Input, Memory};
+use crate::instruction::args::{BinaryOp, BinaryOpArgs, BranchArgs, Comparator, DrawArgs, IndexOp, IndexOpArgs, IndexSource, InputBranchArgs, JumpArgs, RegisterArgs, Source, Target, Timer};
+use crate::types::{Register, Word};
 
 use super::Instruction;
 
@@ -17,14 +19,14 @@ impl OpCode {
 }
 
 impl Display for OpCode {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.0)
     }
 }
 
-impl Into<String> for OpCode {
-    fn into(self) -> String {
-        format!("{}", self)
+impl From<OpCode> for String {
+    fn from(op_code: OpCode) -> Self {
+        format!("{}", op_code)
     }
 }
 
@@ -49,7 +51,7 @@ impl TryFrom<&Instruction> for OpCode {
                     match flow {
                         Flow::Sys { args } => match args.register {
                             Some(_) => Err(Error::NoOpcodeError(instruction.clone()))?,
-                            None => 0x0000 | rest,
+                            None => rest,
                         }
                         Flow::Jump { args } => match &args.register {
                             None => 0x1000 | rest,
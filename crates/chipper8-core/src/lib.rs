@@ -0,0 +1,27 @@
+//! The CHIP-8 interpreter core of Chipper 8: the machine, its instructions and opcodes.
+//!
+//! `no_std` (with `alloc`), so it can be embedded in microcontroller projects and other front-ends.
+//! The `serde` feature makes machine states (de-)serializable, and the `rand` feature seeds the
+//! random number instruction from the operating system (which needs `std`).
+
+#![no_std]
+
+extern crate alloc;
+
+pub use draw_options::DrawOptions;
+pub use errors::{Error, Result};
+pub use instruction::{Instruction, OpCode};
+pub use machine::{Machine, MachineConfig};
+pub use profiler::{Coverage, Profiler, SubroutineProfile};
+pub use stack::{Frame, Stack};
+pub use types::{Address, Timer};
+
+pub mod config;
+mod draw_options;
+pub mod encoding;
+mod errors;
+mod stack;
+mod machine;
+mod profiler;
+pub mod instruction;
+pub mod types;
@@ -1,21 +1,27 @@
-use std::ops::{BitAndAssign, BitOrAssign, BitXorAssign, ShlAssign, ShrAssign};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::{BitAndAssign, BitOrAssign, BitXorAssign, ShlAssign, ShrAssign};
 
 // todo: everywhere use types from machine::types here
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::{Error, Result};
-use crate::assembler::Tokens;
-use crate::machine::instruction::Input;
-use crate::ui::Rom;
+#[cfg(feature = "serde")]
+use crate::encoding;
+use crate::instruction::Input;
 
 use super::config;
 use super::draw_options::DrawOptions;
+use super::profiler::Profiler;
 use super::instruction::{Flow, Graphics, Instruction, Memory, OpCode};
 use super::instruction::args::{self, BinaryOp, BinaryOpArgs, Comparator, IndexOp, IndexOpArgs, IndexSource, Source, Target};
-use super::stack::Stack;
+use super::stack::{Frame, Stack};
 use super::types::{Address, Register, Timer};
 
-#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+// quirks added later default to off in states saved before them
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct MachineConfig {
     pub bitshift_ignore_y: bool,
     pub jump_xnn: bool,
@@ -37,18 +43,37 @@ impl MachineConfig {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+impl Default for MachineConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Machine {
     pub registers: Vec<u8>,
     pub stack: Stack,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::base64_bytes"))]
     pub memory: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_pixels"))]
     pub display: Vec<u8>,
     pub program_counter: Address,
     pub index: Address,
     pub delay_timer: Timer,
     pub sound_timer: Timer,
     pub key_buffer: Option<u8>,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub config: MachineConfig,
+    // execution and memory access counters, when profiling is enabled
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub profiler: Option<Profiler>,
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Machine {
@@ -64,6 +89,7 @@ impl Machine {
             registers: vec![0; config::NUM_REGISTERS],
             key_buffer: None,
             config: MachineConfig::new(),
+            profiler: None,
         };
         machine.memory[config::FONT_RANGE].clone_from_slice(&config::FONT_GLYPHS);
         machine
@@ -79,6 +105,18 @@ impl Machine {
         self.sound_timer = 0;
         self.registers.fill(0);
         self.memory[config::FONT_RANGE].clone_from_slice(&config::FONT_GLYPHS);
+        if let Some(profiler) = &mut self.profiler {
+            profiler.reset();
+        }
+    }
+
+    /// starts collecting a profile from the current program counter, or stops and discards it
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiler = enabled.then(|| {
+            let mut profiler = Profiler::new();
+            profiler.start(u16::from(&self.program_counter));
+            profiler
+        });
     }
 
     pub fn load(&mut self, offset: &Address, data: &[u8]) {
@@ -90,24 +128,14 @@ impl Machine {
         self.memory[offset..offset + data.len()].clone_from_slice(data);
     }
 
-    pub fn load_rom(&mut self, rom: &mut Rom, address: Option<&Address>) {
-        if rom.loaded_at.is_some() {
-            panic!("rom already loaded");
-        }
-        let default_load_address = Address::try_from(0x200u16).unwrap();
-        let address = address.unwrap_or(&default_load_address);
-        rom.loaded_at = Some(usize::from(address));
-        self.load(&address, &rom.bytes);
+    /// loads a program and starts executing it (and profiling it, if enabled) from its first byte
+    pub fn load_program(&mut self, address: &Address, program: &[u8]) {
+        self.load(address, program);
         self.program_counter = address.clone();
-    }
-
-    pub fn unload_rom(&mut self, rom: &mut Rom) {
-        if rom.loaded_at.is_none() {
-            panic!("attempt to unload ROM that was never loaded");
+        if let Some(profiler) = &mut self.profiler {
+            profiler.reset();
+            profiler.start(u16::from(address));
         }
-        self.memory[rom.loaded_range().unwrap()].fill(0);
-        rom.loaded_at = None;
-        // todo: should we move program counter?
     }
 
     pub fn demo(&mut self) -> Result<()> {
@@ -117,7 +145,7 @@ impl Machine {
         self.stack.push(0xBBBu16.try_into().unwrap());
         // put some instructions at these stack addresses show they show in the visualization
         self.set_instruction_at_address(&Address::try_from(0xAAAu16).unwrap(), &Instruction::Graphics(Graphics::Clear))?;
-        self.set_instruction_at_address(&Address::try_from(0xBBBu16).unwrap(), &Instruction::try_from(Tokens::from("font V3")).unwrap())?;
+        self.set_instruction_at_address(&Address::try_from(0xBBBu16).unwrap(), &Instruction::Index { args: IndexOpArgs::font(Register::try_from(3)?) })?;
         self.registers[0] = 0x12;
         self.registers[1] = 0xAB;
         self.delay_timer = 0xF;
@@ -156,6 +184,30 @@ impl Machine {
         self.instruction_at_address(&self.program_counter)
     }
 
+    /// the call stack, innermost frame first: each return address on the stack follows the `call`
+    /// instruction that pushed it, which gives the entry address of the subroutine called
+    pub fn call_frames(&self) -> Vec<Frame> {
+        let return_addresses: Vec<u16> = self.stack.data[..self.stack.pointer].iter()
+            .rev()
+            .map(|address| u16::from(address.as_ref().unwrap()))
+            .collect();
+        let entry = |return_address: u16| {
+            let call_site = Address::try_from(return_address.checked_sub(2)?).ok()?;
+            match self.instruction_at_address(&call_site) {
+                Ok(Instruction::Flow(Flow::Call { args })) => Some(u16::from(&args.address)),
+                _ => None,
+            }
+        };
+        let mut location = u16::from(&self.program_counter);
+        let mut frames = Vec::new();
+        for return_address in return_addresses {
+            frames.push(Frame { entry: entry(return_address), location, return_address: Some(return_address) });
+            location = return_address.wrapping_sub(2);
+        }
+        frames.push(Frame { entry: None, location, return_address: None });
+        frames
+    }
+
     pub fn at_index(&self) -> Option<u8> {
         self.byte_at_address(&self.index)
     }
@@ -181,6 +233,9 @@ impl Machine {
                 let y = self.registers[usize::from(&args.y)] as usize % config::DISPLAY_HEIGHT;
                 let index_start = usize::from(&self.index);
                 let index_end = index_start + usize::from(&args.height);
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.record_read(index_start..index_end);
+                }
                 self.registers[0xF] = if DrawOptions::new(
                     &self.memory[index_start..index_end],
                     &mut self.display,
@@ -194,7 +249,10 @@ impl Machine {
     fn execute_flow(&mut self, flow: &Flow) -> Result<()> {
         match flow {
             Flow::Return => {
-                self.program_counter = self.stack.pop().into();
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.record_return();
+                }
+                self.program_counter = self.stack.pop();
             }
             Flow::Jump { args } | Flow::Call { args } | Flow::Sys { args } => {
                 let mut address = args.address.clone();
@@ -210,6 +268,9 @@ impl Machine {
                     Flow::Call { args: _ } => {
                         // todo: can we swap here?
                         self.stack.push(self.program_counter.clone());
+                        if let Some(profiler) = &mut self.profiler {
+                            profiler.record_call(u16::from(&address));
+                        }
                         self.program_counter = address;
                     }
                     Flow::Sys { args: _ } => {
@@ -295,7 +356,7 @@ impl Machine {
                     self.set_flag(lowest_bit);
                 }
                 BinaryOp::Random => {
-                    *target = source & rand::random::<u8>();
+                    *target = source & random_byte();
                 }
             }
         };
@@ -307,6 +368,12 @@ impl Machine {
             Memory::Load { args } | Memory::Save { args } => args
         };
         let last = usize::from(&args.register) + 1;
+        if let Some(profiler) = &mut self.profiler {
+            match memory {
+                Memory::Load { args: _ } => profiler.record_read(self.index.as_range(last)),
+                Memory::Save { args: _ } => profiler.record_write(self.index.as_range(last)),
+            }
+        }
         let (source, target) = match memory {
             Memory::Load { args: _ } => (&self.memory[self.index.as_range(last)], &mut self.registers[..last]),
             Memory::Save { args: _ } => (&self.registers[..last], &mut self.memory[self.index.as_range(last)]),
@@ -371,11 +438,21 @@ impl Machine {
                 let value = self.registers[usize::from(&args.register)];
                 let digits = [value / 100 % 10, value / 10 % 10, value % 10];
                 self.memory[self.index.as_range(3)].clone_from_slice(&digits);
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.record_write(self.index.as_range(3));
+                }
             }
         };
         Ok(())
     }
 
+    /// counts the delay and sound timers down: front-ends call this at 60Hz, independently of how
+    /// many instructions they execute per frame
+    pub fn tick_timers(&mut self) {
+        self.sound_timer = self.sound_timer.saturating_sub(1);
+        self.delay_timer = self.delay_timer.saturating_sub(1);
+    }
+
     pub fn tick(&mut self) -> Result<()> {
         let instruction = self.next_instruction().unwrap();
         if self.config.auto_exit {
@@ -385,10 +462,29 @@ impl Machine {
                 }
             }
         }
-        self.sound_timer -= if self.sound_timer > 0 { 1 } else { 0 };
-        self.delay_timer -= if self.delay_timer > 0 { 1 } else { 0 };
+        if let Some(profiler) = &mut self.profiler {
+            profiler.record_execution(self.program_counter.as_index());
+        }
         self.program_counter.step();
         self.execute(&instruction)?;
         Ok(())
     }
 }
+
+#[cfg(feature = "rand")]
+fn random_byte() -> u8 {
+    rand::random()
+}
+
+// without an operating system to seed a generator, a xorshift generator with a fixed seed stands in
+#[cfg(not(feature = "rand"))]
+fn random_byte() -> u8 {
+    use core::sync::atomic::{AtomicU32, Ordering};
+    static STATE: AtomicU32 = AtomicU32::new(0x2545_F491);
+    let mut state = STATE.load(Ordering::Relaxed);
+    state ^= state << 13;
+    state ^= state >> 17;
+    state ^= state << 5;
+    STATE.store(state, Ordering::Relaxed);
+    (state >> 24) as u8
+}
@@ -0,0 +1,146 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::config;
+
+/// per-address execution and memory access counters, collected while the machine runs (if enabled)
+#[derive(Debug, Eq, PartialEq)]
+pub struct Profiler {
+    // number of times the instruction at each address was executed
+    pub executions: Vec<u64>,
+    // number of times each byte was read by an instruction (sprites, `load`)
+    pub reads: Vec<u64>,
+    // number of times each byte was written by an instruction (`save`, `bcd`)
+    pub writes: Vec<u64>,
+    // subroutine entry address -> profile of that subroutine
+    pub subroutines: BTreeMap<u16, SubroutineProfile>,
+    // entry addresses of the subroutines currently being executed, innermost last
+    call_stack: Vec<u16>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct SubroutineProfile {
+    pub calls: u64,
+    // instructions executed in the subroutine itself, excluding those it calls
+    pub instructions: u64,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self {
+            executions: vec![0; config::MEMORY_SIZE],
+            reads: vec![0; config::MEMORY_SIZE],
+            writes: vec![0; config::MEMORY_SIZE],
+            subroutines: BTreeMap::new(),
+            call_stack: Vec::new(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// the program's entry point, which profiles instructions executed outside of any subroutine
+    pub fn start(&mut self, address: u16) {
+        self.call_stack = vec![address];
+        self.subroutines.entry(address).or_default().calls += 1;
+    }
+
+    pub fn record_execution(&mut self, address: usize) {
+        self.executions[address] += 1;
+        if let Some(entry) = self.call_stack.last() {
+            self.subroutines.entry(*entry).or_default().instructions += 1;
+        }
+    }
+
+    pub fn record_read(&mut self, range: Range<usize>) {
+        self.reads[range].iter_mut().for_each(|count| *count += 1);
+    }
+
+    pub fn record_write(&mut self, range: Range<usize>) {
+        self.writes[range].iter_mut().for_each(|count| *count += 1);
+    }
+
+    pub fn record_call(&mut self, address: u16) {
+        self.call_stack.push(address);
+        self.subroutines.entry(address).or_default().calls += 1;
+    }
+
+    pub fn record_return(&mut self) {
+        // never pop the entry point, so a stray return still attributes instructions somewhere
+        if self.call_stack.len() > 1 {
+            self.call_stack.pop();
+        }
+    }
+
+    /// addresses by execution count, most executed first
+    pub fn hot_spots(&self) -> Vec<(u16, u64)> {
+        let mut hot_spots: Vec<_> = self.executions.iter().enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(address, count)| (address as u16, *count))
+            .collect();
+        hot_spots.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+        hot_spots
+    }
+
+    pub fn total_executions(&self) -> u64 {
+        self.executions.iter().sum()
+    }
+
+    /// summarises which bytes of the program in `range` were executed or read as data
+    pub fn coverage(&self, range: Range<usize>) -> Coverage {
+        // both bytes of an executed instruction count as code
+        let code = |address: usize| self.executions[address] > 0 || (address > 0 && self.executions[address - 1] > 0);
+        let covered = |address: usize| code(address) || self.reads[address] > 0;
+        let count = |predicate: &dyn Fn(usize) -> bool| range.clone().filter(|address| predicate(*address)).count();
+        let size = range.len();
+        let executed: BTreeMap<_, _> = range.clone()
+            .filter(|address| self.executions[*address] > 0)
+            .map(|address| (address as u16, self.executions[address]))
+            .collect();
+        Coverage {
+            start: range.start as u16,
+            size,
+            executed_instructions: executed.len(),
+            executed_bytes: count(&code),
+            read_bytes: count(&|address| self.reads[address] > 0),
+            written_bytes: count(&|address| self.writes[address] > 0),
+            covered_percent: if size == 0 { 0.0 } else { 100.0 * count(&covered) as f64 / size as f64 },
+            executed,
+            uncovered: range.filter(|address| !covered(*address)).map(|address| address as u16).collect(),
+            subroutines: self.subroutines.clone(),
+        }
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// a coverage report for a program, saved as JSON so ROM tests can check which code they exercised
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Coverage {
+    pub start: u16,
+    pub size: usize,
+    pub executed_instructions: usize,
+    // bytes belonging to executed instructions
+    pub executed_bytes: usize,
+    pub read_bytes: usize,
+    pub written_bytes: usize,
+    // percentage of program bytes either executed or read as data
+    pub covered_percent: f64,
+    // address -> number of executions
+    pub executed: BTreeMap<u16, u64>,
+    // addresses neither executed nor read
+    pub uncovered: Vec<u16>,
+    pub subroutines: BTreeMap<u16, SubroutineProfile>,
+}
@@ -1,9 +1,14 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use super::config;
 use super::types::Address;
 
-#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Stack {
     pub data: Vec<Option<Address>>,
     pub pointer: usize,
@@ -37,4 +42,23 @@ impl Stack {
         self.data.fill(None);
         self.pointer = 0;
     }
-}
\ No newline at end of file
+}
+
+impl Default for Stack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// a frame of the call stack, reconstructed from the return addresses on the stack
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Frame {
+    // address of the subroutine being executed, if known (it never is for the outermost frame, which is
+    // the program itself)
+    pub entry: Option<u16>,
+    // where execution is in this frame: the program counter for the innermost frame, the call site
+    // of the next frame in for the others
+    pub location: u16,
+    // where execution continues when this frame's subroutine returns
+    pub return_address: Option<u16>,
+}
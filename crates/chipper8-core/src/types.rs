@@ -1,6 +1,8 @@
-use std::fmt::{Display, Formatter};
-use std::ops::{Add, Range, Sub};
+use core::fmt::{Display, Formatter};
+use alloc::string::String;
+use core::ops::{Add, Range, Sub};
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use ux::{u12, u4};
 
@@ -12,7 +14,7 @@ pub type Timer = u8;
 pub struct Nibble(pub u4);
 
 impl Display for Nibble {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{:#01X}", self.0)
     }
 }
@@ -48,7 +50,7 @@ impl From<&Nibble> for usize {
 pub struct Byte(pub u8);
 
 impl Display for Byte {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{:#03X}", self.0)
     }
 }
@@ -72,19 +74,27 @@ impl From<&Byte> for usize {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Address(pub u12);
 
+#[cfg(feature = "serde")]
 impl Serialize for Address {
-    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> where S: Serializer {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> where S: Serializer {
         u16::from(self.0).serialize(serializer)
     }
 }
 
+#[cfg(feature = "serde")]
 impl<'de> Deserialize<'de> for Address {
-    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error> where D: Deserializer<'de> {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error> where D: Deserializer<'de> {
         let value = u16::deserialize(deserializer)?;
         Ok(Self::try_from(value).unwrap())
     }
 }
 
+impl Default for Address {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Address {
     pub fn new() -> Self {
         Self(0u8.into())
@@ -112,7 +122,7 @@ impl Address {
 }
 
 impl Display for Address {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{:#05X}", self.0)
     }
 }
@@ -161,7 +171,7 @@ impl From<&Address> for usize {
 pub struct Word(pub u16);
 
 impl Display for Word {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{:#06X}", self.0)
     }
 }
@@ -186,7 +196,7 @@ impl From<&Word> for usize {
 pub struct Register(pub Nibble);
 
 impl Display for Register {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "V{:01X}", self.0.0)
     }
 }
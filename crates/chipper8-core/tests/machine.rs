@@ -0,0 +1,39 @@
+// runs with and without the optional features: `cargo test -p chipper8-core --no-default-features`
+
+use chipper8_core::{Address, Error, Machine};
+
+// v0 := 1; v1 := 2; v2 := random 0x0F; exit
+const PROGRAM: [u8; 8] = [0x60, 0x01, 0x61, 0x02, 0xC2, 0x0F, 0x00, 0xF0];
+
+fn run(machine: &mut Machine) {
+    loop {
+        match machine.tick() {
+            Ok(()) => continue,
+            Err(Error::MachineExit) => return,
+            Err(error) => panic!("{}", error),
+        }
+    }
+}
+
+#[test]
+fn test_run_program() {
+    let mut machine = Machine::new();
+    let address = Address::try_from(0x200u16).unwrap();
+    machine.load_program(&address, &PROGRAM);
+    assert_eq!(machine.memory[0x200..0x208], PROGRAM);
+    assert_eq!(machine.program_counter, address);
+    run(&mut machine);
+    assert_eq!(machine.registers[..2], [1, 2]);
+    assert!(machine.registers[2] <= 0x0F);
+}
+
+#[test]
+fn test_load_program_restarts_profiler() {
+    let mut machine = Machine::new();
+    machine.set_profiling(true);
+    machine.load_program(&Address::try_from(0x300u16).unwrap(), &PROGRAM);
+    run(&mut machine);
+    let coverage = machine.profiler.as_ref().unwrap().coverage(0x300..0x308);
+    assert_eq!(coverage.executed_instructions, 4);
+    assert_eq!(coverage.executed.get(&0x300), Some(&1));
+}
@@ -0,0 +1,99 @@
+use egui::Context;
+
+use crate::emulator::Emulator;
+use crate::repl::Repl;
+use crate::settings::{DisplayOptions, Settings};
+use crate::ui::{KeyCapture, Ui};
+use crate::ui::util::key_capture::KeyBindings;
+use crate::ui::windows::Display;
+
+/// the REPL with its debugger windows, natively and on the web
+pub struct ReplApp {
+    ui: Ui,
+    repl: Repl,
+    // last settings written to (or read from) the settings file
+    settings: Settings,
+}
+
+impl ReplApp {
+    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+        let settings = Settings::load_or_default();
+        let mut ui = Ui::new();
+        ui.set_open_windows(&settings.open_windows);
+        let mut repl = Repl::new();
+        repl.state.key_capture.bindings = KeyBindings::load_or_default();
+        repl.restore(&settings);
+        Self {
+            ui,
+            repl,
+            settings,
+        }
+    }
+
+    fn save_settings(&mut self) {
+        let settings = Settings {
+            open_windows: self.ui.open_windows(),
+            ..self.repl.state.settings()
+        };
+        if settings != self.settings {
+            if let Err(error) = settings.save() {
+                self.repl.state.error = Some(error);
+            }
+            self.settings = settings;
+        }
+    }
+}
+
+impl eframe::App for ReplApp {
+    fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
+        self.repl.update_memory_tags();
+        self.ui.draw(ctx, &self.repl.machine, &mut self.repl.state);
+        self.repl.machine.key_buffer = self.repl.state.key_capture.key();
+        self.repl.execute_buffered();
+        self.save_settings();
+        self.repl.run_frames();
+        if self.repl.state.running {
+            ctx.request_repaint_after(self.repl.state.until_next_frame());
+        }
+        if self.repl.state.gdb.is_some() {
+            self.repl.poll_gdb();
+            ctx.request_repaint_after(self.repl.state.frame_time());
+        }
+    }
+}
+
+/// just the display of a running ROM
+pub struct EmulatorApp {
+    emulator: Emulator,
+    display: Display,
+    key_capture: KeyCapture,
+}
+
+impl EmulatorApp {
+    pub fn new(_cc: &eframe::CreationContext<'_>, emulator: Emulator, display_options: &DisplayOptions) -> Self {
+        let mut key_capture = KeyCapture::new();
+        key_capture.bindings = KeyBindings::load_or_default();
+        key_capture.bindings.set_rom(Some(&emulator.rom_name));
+        let mut display = Display::minimal();
+        display.set_options(display_options);
+        Self {
+            emulator,
+            display,
+            key_capture,
+        }
+    }
+}
+
+impl eframe::App for EmulatorApp {
+    fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(
+            ctx, |ui| {
+                self.display.ui_stateless(ui, &self.emulator.machine, self.emulator.frames);
+                self.key_capture.update(ui);
+            },
+        );
+        self.emulator.machine.key_buffer = self.key_capture.key();
+        self.emulator.tick();
+        ctx.request_repaint_after(self.emulator.until_next_frame());
+    }
+}
@@ -1,3 +1,7 @@
+pub use octo::{compile, Program, PROGRAM_START};
 pub use parser::Tokens;
+pub use symbols::{Symbols, SYMBOLS_EXTENSION};
 
-mod parser;
\ No newline at end of file
+mod octo;
+mod parser;
+mod symbols;
@@ -0,0 +1,125 @@
+use super::lexer::Token;
+
+/// evaluates an Octo `:calc` expression: like Octo, there is no operator precedence and
+/// expressions are evaluated right to left, so parentheses are needed to group terms
+pub fn evaluate(tokens: &[Token], lookup: impl Fn(&str) -> Option<f64>) -> Result<f64, String> {
+    let mut calc = Calc { tokens, position: 0, lookup };
+    let value = calc.expression()?;
+    match calc.tokens.get(calc.position) {
+        None => Ok(value),
+        Some(token) => Err(format!("unexpected `{}` in expression", token.text)),
+    }
+}
+
+struct Calc<'a, F> {
+    tokens: &'a [Token],
+    position: usize,
+    lookup: F,
+}
+
+impl<F: Fn(&str) -> Option<f64>> Calc<'_, F> {
+    fn next(&mut self) -> Option<&str> {
+        let token = self.tokens.get(self.position)?;
+        self.position += 1;
+        Some(token.text.as_str())
+    }
+
+    fn expression(&mut self) -> Result<f64, String> {
+        let lhs = self.term()?;
+        let Some(op) = self.tokens.get(self.position).map(|token| token.text.clone()) else {
+            return Ok(lhs);
+        };
+        if op == ")" {
+            return Ok(lhs);
+        }
+        self.position += 1;
+        let rhs = self.expression()?;
+        binary(&op, lhs, rhs)
+    }
+
+    fn term(&mut self) -> Result<f64, String> {
+        let token = self.next().ok_or("expression ended unexpectedly")?.to_string();
+        if token == "(" {
+            let value = self.expression()?;
+            return match self.next() {
+                Some(")") => Ok(value),
+                _ => Err(String::from("expected `)`")),
+            };
+        }
+        if let Some(value) = parse_number(&token).map(|value| value as f64).or_else(|| (self.lookup)(&token)) {
+            return Ok(value);
+        }
+        let value = self.term().map_err(|_| format!("unknown name `{}` in expression", token))?;
+        unary(&token, value)
+    }
+}
+
+fn binary(op: &str, lhs: f64, rhs: f64) -> Result<f64, String> {
+    let int = |value: f64| value as i64;
+    let boolean = |value: bool| if value { 1.0 } else { 0.0 };
+    Ok(match op {
+        "+" => lhs + rhs,
+        "-" => lhs - rhs,
+        "*" => lhs * rhs,
+        "/" => lhs / rhs,
+        "%" => lhs % rhs,
+        "&" => (int(lhs) & int(rhs)) as f64,
+        "|" => (int(lhs) | int(rhs)) as f64,
+        "^" => (int(lhs) ^ int(rhs)) as f64,
+        "<<" => shift(op, lhs, rhs, i64::checked_shl)?,
+        ">>" => shift(op, lhs, rhs, i64::checked_shr)?,
+        "pow" => lhs.powf(rhs),
+        "min" => lhs.min(rhs),
+        "max" => lhs.max(rhs),
+        "<" => boolean(lhs < rhs),
+        ">" => boolean(lhs > rhs),
+        "<=" => boolean(lhs <= rhs),
+        ">=" => boolean(lhs >= rhs),
+        "==" => boolean(lhs == rhs),
+        "!=" => boolean(lhs != rhs),
+        op => Err(format!("unknown operator `{}` in expression", op))?,
+    })
+}
+
+// shifting by a negative amount or by the width of an i64 or more is an error, not a panic
+fn shift(op: &str, lhs: f64, rhs: f64, shift: fn(i64, u32) -> Option<i64>) -> Result<f64, String> {
+    u32::try_from(rhs as i64).ok()
+        .and_then(|amount| shift(lhs as i64, amount))
+        .map(|value| value as f64)
+        .ok_or_else(|| format!("cannot shift by {} with `{}`", rhs, op))
+}
+
+fn unary(op: &str, value: f64) -> Result<f64, String> {
+    Ok(match op {
+        "-" => -value,
+        "~" => !(value as i64) as f64,
+        "!" => if value == 0.0 { 1.0 } else { 0.0 },
+        "sin" => value.sin(),
+        "cos" => value.cos(),
+        "tan" => value.tan(),
+        "exp" => value.exp(),
+        "log" => value.ln(),
+        "abs" => value.abs(),
+        "sqrt" => value.sqrt(),
+        "sign" => value.signum(),
+        "ceil" => value.ceil(),
+        "floor" => value.floor(),
+        op => Err(format!("unknown name `{}` in expression", op))?,
+    })
+}
+
+/// parses an Octo number literal: decimal, `0x` hex or `0b` binary, optionally negative
+pub fn parse_number(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if let Some(binary) = digits.strip_prefix("0b").or_else(|| digits.strip_prefix("0B")) {
+        i64::from_str_radix(binary, 2).ok()?
+    } else {
+        digits.parse::<i64>().ok()?
+    };
+    Some(if negative { -value } else { value })
+}
@@ -0,0 +1,568 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::{Error, Result};
+use crate::machine::instruction::{Flow, Graphics, Input, Instruction, Memory, OpCode};
+use crate::machine::instruction::args::{BinaryOp, BinaryOpArgs, BranchArgs, Comparator, DrawArgs, IndexOpArgs, InputBranchArgs, JumpArgs, RegisterArgs, Source, Target, Timer};
+use crate::machine::types::{Address, Register};
+
+use super::calc;
+use super::lexer::{self, Token};
+
+/// address programs are loaded at
+pub const PROGRAM_START: u16 = 0x200;
+const ADDRESS_LIMIT: u16 = 0x1000;
+// tokens macros may expand to in total, so a macro that calls itself fails instead of looping forever
+const MACRO_EXPANSION_LIMIT: usize = 1 << 20;
+
+/// the result of compiling an Octo program
+pub struct Program {
+    // ROM bytes, to be loaded at `PROGRAM_START`
+    pub bytes: Vec<u8>,
+    pub labels: BTreeMap<String, u16>,
+    // source line each emitted byte came from
+    pub lines: BTreeMap<u16, usize>,
+}
+
+struct Macro {
+    args: Vec<String>,
+    body: Vec<Token>,
+    calls: usize,
+}
+
+// open control flow blocks, remembering jumps to patch when the block ends
+enum Block {
+    If { jump: u16 },
+    Else { jump: u16 },
+    Loop { start: u16, breaks: Vec<u16> },
+}
+
+// a label used before its definition, patched into the instruction at `address` once known
+struct Fixup {
+    address: u16,
+    label: String,
+    line: usize,
+}
+
+enum Condition {
+    Branch { lhs: Register, rhs: Source, comparator: Comparator },
+    Key { key: Register, comparator: Comparator },
+}
+
+impl Condition {
+    /// instruction skipping the next one if the condition does (or does not) hold
+    fn skip_if(&self, holds: bool) -> Instruction {
+        let comparator = |comparator: &Comparator| match (comparator, holds) {
+            (Comparator::Equal, true) | (Comparator::NotEqual, false) => Comparator::Equal,
+            (Comparator::NotEqual, true) | (Comparator::Equal, false) => Comparator::NotEqual,
+        };
+        match self {
+            Self::Branch { lhs, rhs, comparator: c } => Instruction::Flow(Flow::Branch {
+                args: BranchArgs { lhs: Source::Register(lhs.clone()), rhs: rhs.clone(), comparator: comparator(c) }
+            }),
+            Self::Key { key, comparator: c } => Instruction::Input(Input::Branch {
+                args: InputBranchArgs { key: Source::Register(key.clone()), comparator: comparator(c) }
+            }),
+        }
+    }
+}
+
+pub struct Compiler {
+    tokens: VecDeque<Token>,
+    line: usize,
+    rom: Vec<u8>,
+    here: u16,
+    labels: BTreeMap<String, u16>,
+    lines: BTreeMap<u16, usize>,
+    constants: BTreeMap<String, f64>,
+    aliases: BTreeMap<String, u8>,
+    macros: BTreeMap<String, Macro>,
+    expanded_tokens: usize,
+    fixups: Vec<Fixup>,
+    blocks: Vec<Block>,
+}
+
+impl Compiler {
+    pub fn new(source: &str) -> Self {
+        Self {
+            tokens: lexer::tokenize(source).into(),
+            line: 1,
+            rom: Vec::new(),
+            here: PROGRAM_START,
+            labels: BTreeMap::new(),
+            lines: BTreeMap::new(),
+            constants: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+            macros: BTreeMap::new(),
+            expanded_tokens: 0,
+            fixups: Vec::new(),
+            blocks: Vec::new(),
+        }
+    }
+
+    pub fn compile(mut self) -> Result<Program> {
+        // execution starts at `main`, so jump there unless the program starts with it anyway
+        let texts: Vec<_> = self.tokens.iter().map(|token| token.text.as_str()).collect();
+        let defines_main = texts.windows(2).any(|pair| pair == [":", "main"]);
+        if defines_main && !texts.starts_with(&[":", "main"]) {
+            self.fixups.push(Fixup { address: self.here, label: String::from("main"), line: 1 });
+            self.emit(&jump_to(0))?;
+        }
+        while !self.tokens.is_empty() {
+            self.statement()?;
+        }
+        if !self.blocks.is_empty() {
+            return Err(self.error("unterminated `if ... begin` or `loop` at end of program"));
+        }
+        for fixup in std::mem::take(&mut self.fixups) {
+            let address = *self.labels.get(&fixup.label).ok_or_else(|| Error::AssemblyError {
+                line: fixup.line,
+                message: format!("undefined label `{}`", fixup.label),
+            })?;
+            self.patch(fixup.address, address);
+        }
+        Ok(Program { bytes: self.rom, labels: self.labels, lines: self.lines })
+    }
+
+    fn error(&self, message: impl Into<String>) -> Error {
+        Error::AssemblyError { line: self.line, message: message.into() }
+    }
+
+    fn next(&mut self) -> Result<String> {
+        let token = self.tokens.pop_front().ok_or_else(|| self.error("unexpected end of program"))?;
+        self.line = token.line;
+        Ok(token.text)
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.front().map(|token| token.text.as_str())
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<()> {
+        match self.next()? {
+            text if text == expected => Ok(()),
+            text => Err(self.error(format!("expected `{}`, found `{}`", expected, text))),
+        }
+    }
+
+    fn statement(&mut self) -> Result<()> {
+        let text = self.next()?;
+        match text.as_str() {
+            ":" => {
+                let name = self.next()?;
+                if self.labels.insert(name.clone(), self.here).is_some() {
+                    return Err(self.error(format!("label `{}` defined twice", name)));
+                }
+            }
+            ":const" => {
+                let name = self.next()?;
+                let value = self.number()?;
+                self.constants.insert(name, value);
+            }
+            ":alias" => {
+                let name = self.next()?;
+                let register = self.register()?;
+                self.aliases.insert(name, u8::from(&register));
+            }
+            ":calc" => {
+                let name = self.next()?;
+                self.expect("{")?;
+                let value = self.calc()?;
+                self.constants.insert(name, value);
+            }
+            ":macro" => self.define_macro()?,
+            ":org" => self.here = self.address_value()?,
+            ":byte" => {
+                let value = self.byte()?;
+                self.emit_byte(value)?;
+            }
+            ":call" => self.emit_with_address(call)?,
+            ":unpack" => {
+                let nibble = self.number()? as u16 & 0xF;
+                let address = self.address_value()?;
+                self.emit(&assign(0, (nibble << 4 | address >> 8) as u8))?;
+                self.emit(&assign(1, address as u8))?;
+            }
+            // debugger directives
+            ":breakpoint" => { self.next()?; }
+            ":monitor" => {
+                self.next()?;
+                self.next()?;
+            }
+            "clear" => { self.emit(&Instruction::Graphics(Graphics::Clear))?; }
+            "return" | ";" => { self.emit(&Instruction::Flow(Flow::Return))?; }
+            "bcd" => {
+                let register = self.register()?;
+                self.emit(&Instruction::BinaryCodedDecimal { args: RegisterArgs { register } })?;
+            }
+            "save" | "load" => {
+                let args = RegisterArgs { register: self.register()? };
+                self.emit(&Instruction::Memory(if text == "save" { Memory::Save { args } } else { Memory::Load { args } }))?;
+            }
+            "sprite" => {
+                let (x, y) = (self.register()?, self.register()?);
+                let height = (self.number()? as u8).try_into().map_err(|error: chipper8_core::Error| self.error(error.to_string()))?;
+                self.emit(&Instruction::Graphics(Graphics::Draw { args: DrawArgs { x, y, height } }))?;
+            }
+            "jump" => self.emit_with_address(jump)?,
+            "jump0" => self.emit_with_address(|address| Instruction::Flow(Flow::Jump {
+                args: JumpArgs { address, register: Some(Register::try_from(0).unwrap()) }
+            }))?,
+            "native" => self.emit_with_address(|address| Instruction::Flow(Flow::Sys {
+                args: JumpArgs { address, register: None }
+            }))?,
+            "delay" | "buzzer" => {
+                self.expect(":=")?;
+                let timer = if text == "delay" { Timer::Delay } else { Timer::Sound };
+                let source = Source::Register(self.register()?);
+                self.emit(&Instruction::Arithmetic { args: BinaryOpArgs { target: Target::Timer(timer), source, op: BinaryOp::Assign } })?;
+            }
+            "i" => self.index()?,
+            "if" => self.conditional()?,
+            "else" => {
+                let Some(Block::If { jump: if_jump }) = self.blocks.pop() else {
+                    return Err(self.error("`else` without `if ... begin`"));
+                };
+                let else_jump = self.emit(&jump_to(0))?;
+                self.patch(if_jump, self.here);
+                self.blocks.push(Block::Else { jump: else_jump });
+            }
+            "end" => match self.blocks.pop() {
+                Some(Block::If { jump } | Block::Else { jump }) => self.patch(jump, self.here),
+                _ => return Err(self.error("`end` without `if ... begin`")),
+            },
+            "loop" => self.blocks.push(Block::Loop { start: self.here, breaks: Vec::new() }),
+            "while" => {
+                let condition = self.condition()?;
+                self.emit(&condition.skip_if(true))?;
+                let exit = self.emit(&jump_to(0))?;
+                match self.blocks.iter_mut().rev().find(|block| matches!(block, Block::Loop { .. })) {
+                    Some(Block::Loop { breaks, .. }) => breaks.push(exit),
+                    _ => return Err(self.error("`while` outside `loop`")),
+                };
+            }
+            "again" => {
+                let Some(Block::Loop { start, breaks }) = self.blocks.pop() else {
+                    return Err(self.error("`again` without `loop`"));
+                };
+                self.emit(&jump_to(start))?;
+                for address in breaks {
+                    self.patch(address, self.here);
+                }
+            }
+            _ if self.is_register(&text) => self.arithmetic(&text)?,
+            _ if text.starts_with(':') => return Err(self.error(format!("unsupported directive `{}`", text))),
+            _ => {
+                if let Some(value) = calc::parse_number(&text) {
+                    self.emit_byte(self.wrap_byte(value as f64)?)?;
+                } else if self.constants.contains_key(&text) {
+                    let value = self.constants[&text];
+                    self.emit_byte(self.wrap_byte(value)?)?;
+                } else if self.macros.contains_key(&text) {
+                    self.expand_macro(&text)?;
+                } else {
+                    // bare label names are subroutine calls
+                    self.tokens.push_front(Token::new(text, self.line));
+                    self.emit_with_address(call)?;
+                }
+            }
+        };
+        Ok(())
+    }
+
+    fn index(&mut self) -> Result<()> {
+        let op = self.next()?;
+        match op.as_str() {
+            ":=" if self.peek() == Some("hex") => {
+                self.next()?;
+                let register = self.register()?;
+                self.emit(&Instruction::Index { args: IndexOpArgs::font(register) })?;
+            }
+            ":=" => self.emit_with_address(|address| Instruction::Index { args: IndexOpArgs::assign(address) })?,
+            "+=" => {
+                let register = self.register()?;
+                self.emit(&Instruction::Index { args: IndexOpArgs::add(register) })?;
+            }
+            op => return Err(self.error(format!("unsupported index operation `{}`", op))),
+        };
+        Ok(())
+    }
+
+    fn arithmetic(&mut self, target: &str) -> Result<()> {
+        let target = self.parse_register(target).unwrap();
+        let op = self.next()?;
+        let rhs = self.next()?;
+        let source = match (op.as_str(), rhs.as_str()) {
+            (":=", "key") => {
+                return self.emit(&Instruction::Input(Input::Await { args: RegisterArgs { register: target } })).map(|_| ());
+            }
+            (":=", "delay") => Source::Timer(Timer::Delay),
+            (":=", "random") => {
+                let mask = self.byte()?;
+                let args = BinaryOpArgs { target: Target::Register(target), source: Source::Byte(mask.into()), op: BinaryOp::Random };
+                return self.emit(&Instruction::Arithmetic { args }).map(|_| ());
+            }
+            _ => match self.parse_register(&rhs) {
+                Some(register) => Source::Register(register),
+                None => {
+                    self.tokens.push_front(Token::new(rhs, self.line));
+                    Source::Byte(self.byte()?.into())
+                }
+            },
+        };
+        let op = match (op.as_str(), &source) {
+            (":=", _) => BinaryOp::Assign,
+            ("+=", Source::Byte(_)) => BinaryOp::AddWrapping,
+            ("+=", _) => BinaryOp::Add,
+            ("-=", Source::Byte(byte)) => {
+                // there is no subtract immediate: add the two's complement instead
+                let args = BinaryOpArgs {
+                    target: Target::Register(target),
+                    source: Source::Byte(u8::from(byte).wrapping_neg().into()),
+                    op: BinaryOp::AddWrapping,
+                };
+                return self.emit(&Instruction::Arithmetic { args }).map(|_| ());
+            }
+            ("-=", _) => BinaryOp::Subtract,
+            ("=-", _) => BinaryOp::SubtractAlt,
+            ("|=", _) => BinaryOp::BitOr,
+            ("&=", _) => BinaryOp::BitAnd,
+            ("^=", _) => BinaryOp::BitXor,
+            (">>=", _) => BinaryOp::BitShiftRight,
+            ("<<=", _) => BinaryOp::BitShiftLeft,
+            (op, _) => return Err(self.error(format!("unsupported register operation `{}`", op))),
+        };
+        let args = BinaryOpArgs { target: Target::Register(target), source, op };
+        self.emit(&Instruction::Arithmetic { args }).map_err(|_| self.error("no such instruction"))?;
+        Ok(())
+    }
+
+    fn conditional(&mut self) -> Result<()> {
+        let condition = self.condition()?;
+        match self.next()?.as_str() {
+            // skip the following statement unless the condition holds
+            "then" => { self.emit(&condition.skip_if(false))?; }
+            "begin" => {
+                self.emit(&condition.skip_if(true))?;
+                let jump = self.emit(&jump_to(0))?;
+                self.blocks.push(Block::If { jump });
+            }
+            text => return Err(self.error(format!("expected `then` or `begin`, found `{}`", text))),
+        };
+        Ok(())
+    }
+
+    fn condition(&mut self) -> Result<Condition> {
+        let lhs = self.register()?;
+        let op = self.next()?;
+        match op.as_str() {
+            "key" => Ok(Condition::Key { key: lhs, comparator: Comparator::Equal }),
+            "-key" => Ok(Condition::Key { key: lhs, comparator: Comparator::NotEqual }),
+            "==" | "!=" => {
+                let comparator = if op == "==" { Comparator::Equal } else { Comparator::NotEqual };
+                let rhs = match self.peek().and_then(|text| self.parse_register(text)) {
+                    Some(register) => {
+                        self.next()?;
+                        Source::Register(register)
+                    }
+                    None => Source::Byte(self.byte()?.into()),
+                };
+                Ok(Condition::Branch { lhs, rhs, comparator })
+            }
+            op => Err(self.error(format!("unsupported comparison `{}`", op))),
+        }
+    }
+
+    fn define_macro(&mut self) -> Result<()> {
+        let name = self.next()?;
+        let mut args = Vec::new();
+        loop {
+            match self.next()? {
+                text if text == "{" => break,
+                text => args.push(text),
+            }
+        }
+        let mut body = Vec::new();
+        let mut depth = 1;
+        while depth > 0 {
+            let token = self.tokens.pop_front().ok_or_else(|| self.error(format!("unterminated macro `{}`", name)))?;
+            match token.text.as_str() {
+                "{" => depth += 1,
+                "}" => depth -= 1,
+                _ => {}
+            };
+            if depth > 0 {
+                body.push(token);
+            }
+        }
+        self.macros.insert(name, Macro { args, body, calls: 0 });
+        Ok(())
+    }
+
+    fn expand_macro(&mut self, name: &str) -> Result<()> {
+        let count = self.macros[name].args.len();
+        let values = (0..count).map(|_| self.next()).collect::<Result<Vec<_>>>()?;
+        let line = self.line;
+        self.expanded_tokens += self.macros[name].body.len();
+        if self.expanded_tokens > MACRO_EXPANSION_LIMIT {
+            return Err(self.error(format!("too many macro expansions, does `{}` call itself?", name)));
+        }
+        let definition = self.macros.get_mut(name).unwrap();
+        let calls = definition.calls.to_string();
+        definition.calls += 1;
+        let expanded: Vec<_> = definition.body.iter().map(|token| {
+            let text = match definition.args.iter().position(|arg| *arg == token.text) {
+                Some(index) => values[index].clone(),
+                None if token.text == "CALLS" => calls.clone(),
+                None => token.text.clone(),
+            };
+            Token::new(text, line)
+        }).collect();
+        for token in expanded.into_iter().rev() {
+            self.tokens.push_front(token);
+        }
+        Ok(())
+    }
+
+    // evaluates a `:calc` expression up to the closing brace
+    fn calc(&mut self) -> Result<f64> {
+        let mut expression = Vec::new();
+        loop {
+            let token = self.tokens.pop_front().ok_or_else(|| self.error("unterminated expression"))?;
+            self.line = token.line;
+            if token.text == "}" { break; }
+            expression.push(token);
+        }
+        calc::evaluate(&expression, |name| self.lookup(name)).map_err(|message| self.error(message))
+    }
+
+    fn lookup(&self, name: &str) -> Option<f64> {
+        match name {
+            "HERE" => Some(self.here as f64),
+            "PI" => Some(std::f64::consts::PI),
+            "E" => Some(std::f64::consts::E),
+            _ => self.constants.get(name).copied()
+                .or_else(|| self.labels.get(name).map(|address| *address as f64)),
+        }
+    }
+
+    // a numeric literal, constant, label or braced expression
+    fn number(&mut self) -> Result<f64> {
+        let text = self.next()?;
+        if text == "{" {
+            return self.calc();
+        }
+        calc::parse_number(&text).map(|value| value as f64)
+            .or_else(|| self.lookup(&text))
+            .ok_or_else(|| self.error(format!("expected a number, found `{}`", text)))
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        let value = self.number()?;
+        self.wrap_byte(value)
+    }
+
+    fn wrap_byte(&self, value: f64) -> Result<u8> {
+        let value = value as i64;
+        if (-128..=255).contains(&value) {
+            Ok(value as u8)
+        } else {
+            Err(self.error(format!("{} does not fit in a byte", value)))
+        }
+    }
+
+    fn address_value(&mut self) -> Result<u16> {
+        let value = self.number()? as i64;
+        if (0..ADDRESS_LIMIT as i64).contains(&value) {
+            Ok(value as u16)
+        } else {
+            Err(self.error(format!("{} is not a valid address", value)))
+        }
+    }
+
+    fn is_register(&self, text: &str) -> bool {
+        self.parse_register(text).is_some()
+    }
+
+    fn parse_register(&self, text: &str) -> Option<Register> {
+        let index = match self.aliases.get(text) {
+            Some(index) => *index,
+            None => {
+                let digit = text.strip_prefix('v').or_else(|| text.strip_prefix('V'))?;
+                if digit.len() != 1 { return None; }
+                u8::from_str_radix(digit, 16).ok()?
+            }
+        };
+        Register::try_from(index).ok()
+    }
+
+    fn register(&mut self) -> Result<Register> {
+        let text = self.next()?;
+        self.parse_register(&text).ok_or_else(|| self.error(format!("expected a register, found `{}`", text)))
+    }
+
+    // emits an instruction taking an address, which may be a label defined later on
+    fn emit_with_address(&mut self, instruction: impl Fn(Address) -> Instruction) -> Result<()> {
+        let text = self.peek().unwrap_or_default().to_string();
+        let address = if text == "{" || calc::parse_number(&text).is_some() || self.lookup(&text).is_some() {
+            self.address_value()?
+        } else {
+            self.next()?;
+            self.fixups.push(Fixup { address: self.here, label: text, line: self.line });
+            0
+        };
+        self.emit(&instruction(Address::try_from(address)?))?;
+        Ok(())
+    }
+
+    /// emits an instruction, returning its address
+    fn emit(&mut self, instruction: &Instruction) -> Result<u16> {
+        let address = self.here;
+        for byte in OpCode::try_from(instruction)?.bytes() {
+            self.emit_byte(byte)?;
+        }
+        Ok(address)
+    }
+
+    fn emit_byte(&mut self, byte: u8) -> Result<()> {
+        if !(PROGRAM_START..ADDRESS_LIMIT).contains(&self.here) {
+            return Err(self.error(format!("address {:#05X} is outside program memory", self.here)));
+        }
+        let index = (self.here - PROGRAM_START) as usize;
+        if index >= self.rom.len() {
+            self.rom.resize(index + 1, 0);
+        }
+        self.rom[index] = byte;
+        self.lines.insert(self.here, self.line);
+        self.here += 1;
+        Ok(())
+    }
+
+    // sets the address operand of the instruction at `at`
+    fn patch(&mut self, at: u16, address: u16) {
+        let index = (at - PROGRAM_START) as usize;
+        self.rom[index] = (self.rom[index] & 0xF0) | (address >> 8) as u8;
+        self.rom[index + 1] = address as u8;
+    }
+}
+
+fn jump(address: Address) -> Instruction {
+    Instruction::Flow(Flow::Jump { args: JumpArgs { address, register: None } })
+}
+
+fn jump_to(address: u16) -> Instruction {
+    jump(Address::try_from(address).unwrap())
+}
+
+fn call(address: Address) -> Instruction {
+    Instruction::Flow(Flow::Call { args: JumpArgs { address, register: None } })
+}
+
+fn assign(register: u8, value: u8) -> Instruction {
+    Instruction::Arithmetic {
+        args: BinaryOpArgs {
+            target: Target::Register(Register::try_from(register).unwrap()),
+            source: Source::Byte(value.into()),
+            op: BinaryOp::Assign,
+        }
+    }
+}
@@ -0,0 +1,20 @@
+/// a whitespace separated word of Octo source, remembering the line it came from
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Token {
+    pub text: String,
+    pub line: usize,
+}
+
+impl Token {
+    pub fn new(text: impl Into<String>, line: usize) -> Self {
+        Self { text: text.into(), line }
+    }
+}
+
+/// splits Octo source into tokens, dropping `#` comments (which run to the end of the line)
+pub fn tokenize(source: &str) -> Vec<Token> {
+    source.lines().enumerate().flat_map(|(index, line)| {
+        let code = line.split('#').next().unwrap_or_default();
+        code.split_whitespace().map(move |text| Token::new(text, index + 1))
+    }).collect()
+}
@@ -0,0 +1,17 @@
+pub use compiler::{Program, PROGRAM_START};
+
+use crate::Result;
+
+use compiler::Compiler;
+
+mod calc;
+mod compiler;
+mod lexer;
+
+#[cfg(test)]
+mod tests;
+
+/// compiles Octo assembly source to ROM bytes
+pub fn compile(source: &str) -> Result<Program> {
+    Compiler::new(source).compile()
+}
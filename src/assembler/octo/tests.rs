@@ -0,0 +1,101 @@
+use crate::Error;
+use crate::assembler::Symbols;
+
+use super::compile;
+
+fn assemble(source: &str) -> Vec<u8> {
+    compile(source).unwrap().bytes
+}
+
+#[test]
+fn compile_statements_and_labels() {
+    let source = "
+        : main
+          v0 := 5
+          v1 += 2
+          i := sprite   # forward reference
+          sprite v0 v1 3
+          loop
+            v2 := key
+            if v2 == 1 then v0 += 1
+          again
+        : sprite
+          0x80 0xC0 0xE0
+    ";
+    assert_eq!(assemble(source), vec![
+        0x60, 0x05, 0x71, 0x02, 0xA2, 0x10, 0xD0, 0x13,
+        0xF2, 0x0A, 0x42, 0x01, 0x70, 0x01, 0x12, 0x08,
+        0x80, 0xC0, 0xE0,
+    ]);
+}
+
+#[test]
+fn compile_directives_and_blocks() {
+    let source = "
+        :const SIZE 4
+        :alias x v3
+        :calc DOUBLE { SIZE * 2 }
+        :macro set reg val { reg := val }
+        : helper
+          return
+        : main
+          set x DOUBLE
+          if x != SIZE begin
+            x -= 1
+          else
+            helper
+          end
+          loop
+            while x != 0
+            x -= 1
+          again
+    ";
+    assert_eq!(assemble(source), vec![
+        0x12, 0x04, 0x00, 0xEE, 0x63, 0x08, 0x43, 0x04,
+        0x12, 0x0E, 0x73, 0xFF, 0x12, 0x10, 0x22, 0x02,
+        0x43, 0x00, 0x12, 0x18, 0x73, 0xFF, 0x12, 0x10,
+    ]);
+}
+
+#[test]
+fn calc_evaluates_right_to_left() {
+    assert_eq!(assemble(":byte { 1 + 2 * 3 } :byte { 2 * 3 + 1 } :byte { ( 2 * 3 ) + 1 }"), vec![7, 8, 7]);
+}
+
+#[test]
+fn calc_rejects_bad_shifts() {
+    assert_eq!(assemble(":byte { 1 << 7 } :byte { 0x80 >> 7 }"), vec![0x80, 1]);
+    for expression in ["1 << 64", "1 << -1", "1 >> 64"] {
+        let source = format!(":calc x {{ {} }}", expression);
+        assert!(matches!(compile(&source), Err(Error::AssemblyError { .. })), "{}", expression);
+    }
+}
+
+#[test]
+fn recursive_macro_is_an_error() {
+    match compile(":macro m { m }\nm\n") {
+        Err(Error::AssemblyError { line, .. }) => assert_eq!(line, 2),
+        _ => panic!("expected assembly error"),
+    }
+}
+
+#[test]
+fn undefined_label_reports_line() {
+    match compile(": main\n  jump nowhere\n") {
+        Err(Error::AssemblyError { line, .. }) => assert_eq!(line, 2),
+        _ => panic!("expected assembly error"),
+    }
+}
+
+#[test]
+fn line_map_and_symbols() {
+    let source = ": main\n  v0 := 1\n  sub\n: sub\n  v1 := 2\n  return\n";
+    let program = compile(source).unwrap();
+    assert_eq!(program.lines.get(&0x202), Some(&3));
+    assert_eq!(program.lines.get(&0x204), Some(&5));
+    let symbols = Symbols::new(&program, None, source);
+    assert_eq!(symbols.address_of("sub"), Some(0x204));
+    assert_eq!(symbols.label_at(0x206).as_deref(), Some("sub+2"));
+    assert_eq!(symbols.source_at(0x206), Some("return"));
+    assert_eq!(symbols.label_at(0x1FE), None);
+}
@@ -108,7 +108,7 @@ impl TryFrom<Token<'_>> for Register {
         match token {
             Token::Register(s) => {
                 let value = u8::from_str_radix(s, 16)?;
-                value.try_into()
+                Ok(value.try_into()?)
             }
             x => Err(Error::SyntaxError(format!("expected register, found {:?}", x))),
         }
@@ -1,5 +1,5 @@
 use crate::{Error, Result};
-use crate::command::{Command, MachineState, MetaCommand};
+use crate::command::{Command, Location, MachineState, MetaCommand, Profiling, Tracing};
 use crate::machine::Address;
 
 use super::{Token, Tokens};
@@ -57,12 +57,82 @@ impl TryFrom<Tokens<'_>> for MetaCommand {
             Some(Token::Meta(":play")) => Ok(MetaCommand::Play),
             Some(Token::Meta(":pause")) => Ok(MetaCommand::Pause),
             Some(Token::Meta(":play-pause")) => Ok(MetaCommand::PlayPause),
+            Some(Token::Meta(":finish")) => Ok(MetaCommand::Finish),
+            Some(Token::Meta(":next")) => Ok(MetaCommand::Next),
+            Some(Token::Meta(":until")) => Ok(MetaCommand::Until(location(":until", tokens.next())?)),
+            Some(Token::Meta(":run")) => match tokens.next() {
+                Some(Token::Other(ticks)) => match ticks.parse()? {
+                    0 => Err(Error::MetaSyntaxError(String::from(":run requires at least one instruction"))),
+                    ticks => Ok(MetaCommand::Run(ticks)),
+                },
+                Some(x) => Err(Error::MetaSyntaxError(format!(":run requires a number of instructions but got {:?}", x))),
+                None => Err(Error::MetaSyntaxError(String::from(":run requires a number of instructions"))),
+            },
+            Some(Token::Meta(":gdb")) => match tokens.next() {
+                Some(Token::Other("off")) => Ok(MetaCommand::Gdb(None)),
+                Some(Token::Other(port)) => Ok(MetaCommand::Gdb(Some(port.parse()?))),
+                Some(x) => Err(Error::MetaSyntaxError(format!(":gdb requires a port or off but got {:?}", x))),
+                None => Err(Error::MetaSyntaxError(String::from(":gdb requires a port or off"))),
+            },
+            Some(Token::Meta(":symbols")) => match tokens.next() {
+                Some(Token::Other(path)) => Ok(MetaCommand::LoadSymbols(path.into())),
+                Some(x) => Err(Error::MetaSyntaxError(format!(":symbols requires a path but got {:?}", x))),
+                None => Err(Error::MetaSyntaxError(String::from(":symbols requires a path"))),
+            },
+            Some(Token::Meta(":break")) => Ok(MetaCommand::Break(location(":break", tokens.next())?)),
+            Some(Token::Meta(":unbreak")) => Ok(MetaCommand::Unbreak(location(":unbreak", tokens.next())?)),
+            Some(Token::Meta(":tag")) => {
+                let (Some(start), Some(end)) = (tokens.next(), tokens.next()) else {
+                    return Err(Error::MetaSyntaxError(String::from(":tag requires a start address, end address and name")));
+                };
+                let (start, end) = (Address::try_from(start)?, Address::try_from(end)?);
+                Ok(MetaCommand::Tag(start, end, tag_name(":tag", tokens.into())?))
+            }
+            Some(Token::Meta(":profile")) => match tokens.next() {
+                Some(Token::Other("on")) | None => Ok(MetaCommand::Profile(Profiling::On)),
+                Some(Token::Other("off")) => Ok(MetaCommand::Profile(Profiling::Off)),
+                Some(Token::Other("reset")) => Ok(MetaCommand::Profile(Profiling::Reset)),
+                Some(x) => Err(Error::MetaSyntaxError(format!(":profile expects on, off or reset but got {:?}", x))),
+            },
+            Some(Token::Meta(":coverage")) => match tokens.next() {
+                Some(Token::Other(path)) => Ok(MetaCommand::Coverage(path.into())),
+                Some(x) => Err(Error::MetaSyntaxError(format!(":coverage requires a path but got {:?}", x))),
+                None => Err(Error::MetaSyntaxError(String::from(":coverage requires a path"))),
+            },
+            Some(Token::Meta(":trace")) => match tokens.next() {
+                Some(Token::Other("on")) | None => Ok(MetaCommand::Trace(Tracing::On)),
+                Some(Token::Other("off")) => Ok(MetaCommand::Trace(Tracing::Off)),
+                Some(Token::Other("clear")) => Ok(MetaCommand::Trace(Tracing::Clear)),
+                Some(Token::Other(path)) => Ok(MetaCommand::Trace(Tracing::File(path.into()))),
+                Some(x) => Err(Error::MetaSyntaxError(format!(":trace expects on, off, clear or a path but got {:?}", x))),
+            },
+            Some(Token::Meta(":untag")) => Ok(MetaCommand::Untag(tag_name(":untag", tokens.into())?)),
             Some(Token::Meta(s)) => Err(Error::MetaSyntaxError(format!("invalid meta command '{}'", s))),
             s => Err(Error::MetaSyntaxError(format!("expected meta command token but found '{:?}'", s))),
         }
     }
 }
 
+// an address, or a label to be looked up in the loaded symbols
+fn location(command: &str, token: Option<Token>) -> Result<Location> {
+    match token {
+        Some(Token::Other(label)) if !label.starts_with(|c: char| c.is_ascii_digit()) => {
+            Ok(Location::Label(label.into()))
+        }
+        Some(token) => Ok(Location::Address(Address::try_from(token)?)),
+        None => Err(Error::MetaSyntaxError(format!("{} requires an address or label", command))),
+    }
+}
+
+// the rest of the command, which may be quoted to make it clear spaces are part of the name
+fn tag_name(command: &str, name: String) -> Result<String> {
+    let name = name.trim_matches('"');
+    if name.is_empty() {
+        return Err(Error::MetaSyntaxError(format!("{} requires a tag name", command)));
+    }
+    Ok(String::from(name))
+}
+
 impl TryInto<Option<MachineState>> for Tokens<'_> {
     type Error = Error;
 
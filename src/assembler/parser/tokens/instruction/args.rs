@@ -152,15 +152,12 @@ impl TryFrom<Tokens<'_>> for RegisterArgs {
     }
 }
 
-impl TryFrom<Option<Token<'_>>> for Comparator {
-    type Error = Error;
-
-    fn try_from(token: Option<Token<'_>>) -> Result<Self> {
-        match token {
-            Some(Token::Other(s)) if s == "!" => Ok(Self::NotEqual),
-            Some(x) => Err(Error::SyntaxError(format!("expected a comparator, got {:?}", x))),
-            None => Ok(Self::Equal)
-        }
+// a free function, as the orphan rule won't allow a `TryFrom` impl for the core's `Comparator`
+fn comparator(token: Option<Token<'_>>) -> Result<Comparator> {
+    match token {
+        Some(Token::Other(s)) if s == "!" => Ok(Comparator::NotEqual),
+        Some(x) => Err(Error::SyntaxError(format!("expected a comparator, got {:?}", x))),
+        None => Ok(Comparator::Equal)
     }
 }
 
@@ -174,7 +171,7 @@ impl TryFrom<Tokens<'_>> for BranchArgs {
         let rhs = Source::try_from(tokens.next().ok_or(
             Error::SyntaxError(String::from("conditional requires a RHS expression"))
         )?)?;
-        let comparator = Comparator::try_from(tokens.next())?;
+        let comparator = comparator(tokens.next())?;
         Ok(Self { lhs, rhs, comparator })
     }
 }
@@ -186,7 +183,7 @@ impl TryFrom<Tokens<'_>> for InputBranchArgs {
         let key = Source::try_from(tokens.next().ok_or(
             Error::SyntaxError(String::from("branch requires a key"))
         )?)?;
-        let comparator = Comparator::try_from(tokens.next())?;
+        let comparator = comparator(tokens.next())?;
         Ok(Self { key, comparator })
     }
 }
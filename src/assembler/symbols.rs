@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::storage::{FileStorage, Storage};
+
+use super::Program;
+
+/// file extension of symbol maps, saved next to the assembled ROM
+pub const SYMBOLS_EXTENSION: &str = "sym";
+
+/// label names and source lines of an assembled program, used to annotate addresses while debugging
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Symbols {
+    // the source file the program was assembled from
+    pub source: Option<PathBuf>,
+    pub labels: BTreeMap<String, u16>,
+    // source line each ROM byte came from
+    pub lines: BTreeMap<u16, usize>,
+    // text of the source file, if it could be read
+    #[serde(skip)]
+    pub source_lines: Vec<String>,
+}
+
+impl Symbols {
+    pub fn new(program: &Program, source: Option<PathBuf>, source_text: &str) -> Self {
+        Self {
+            source,
+            labels: program.labels.clone(),
+            lines: program.lines.clone(),
+            source_lines: source_text.lines().map(String::from).collect(),
+        }
+    }
+
+    /// path of the symbol map belonging to the ROM at `rom_path`
+    pub fn path_for(rom_path: impl AsRef<Path>) -> PathBuf {
+        rom_path.as_ref().with_extension(SYMBOLS_EXTENSION)
+    }
+
+    /// loads a symbol map and the source file it refers to (relative to the map's directory)
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::load_from(&FileStorage, path.as_ref())
+    }
+
+    pub fn load_from(storage: &dyn Storage, path: &Path) -> Result<Self> {
+        let mut symbols: Self = serde_json::from_str(&storage.read_to_string(path)?)?;
+        if let Some(source) = &symbols.source {
+            let source = path.parent().map_or_else(|| source.clone(), |directory| directory.join(source));
+            if let Ok(text) = storage.read_to_string(&source) {
+                symbols.source_lines = text.lines().map(String::from).collect();
+            }
+        }
+        Ok(symbols)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// the address of a label
+    pub fn address_of(&self, label: &str) -> Option<u16> {
+        self.labels.get(label).copied()
+    }
+
+    /// the label at or closest before `address`, as `label` or `label+offset`
+    pub fn label_at(&self, address: u16) -> Option<String> {
+        let (name, label_address) = self.labels.iter()
+            .filter(|(_, label_address)| **label_address <= address)
+            .max_by_key(|(_, label_address)| **label_address)?;
+        Some(match address - label_address {
+            0 => name.clone(),
+            offset => format!("{}+{}", name, offset),
+        })
+    }
+
+    /// the source line number `address` was assembled from
+    pub fn line_at(&self, address: u16) -> Option<usize> {
+        self.lines.get(&address).copied()
+    }
+
+    /// the source line `address` was assembled from, trimmed
+    pub fn source_at(&self, address: u16) -> Option<&str> {
+        let line = self.line_at(address)?;
+        // lines count from 1, but a hand-edited symbols file might say otherwise
+        line.checked_sub(1).and_then(|line| self.source_lines.get(line)).map(|text| text.trim())
+    }
+}
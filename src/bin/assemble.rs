@@ -0,0 +1,53 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+
+use chipper8::Result;
+use chipper8::assembler::{self, Symbols};
+
+/// Assembles an Octo source file into a CHIP-8 ROM and a symbol map for debugging it in the REPL
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Octo source file (`.8o`)
+    #[arg(index = 1)]
+    source: PathBuf,
+
+    /// ROM file to write (defaults to the source path with a `.ch8` extension)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// do not write the symbol map next to the ROM
+    #[arg(long, default_value_t = false)]
+    no_symbols: bool,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let source = fs::read_to_string(&args.source)?;
+    let program = assembler::compile(&source)?;
+    let output = args.output.unwrap_or_else(|| args.source.with_extension("ch8"));
+    fs::write(&output, &program.bytes)?;
+    println!("wrote {} bytes to {}", program.bytes.len(), output.display());
+    if !args.no_symbols {
+        // the map refers to the source relative to its own directory, so both can be moved together
+        let relative = relative_to(&args.source, &output);
+        let symbols = Symbols::new(&program, Some(relative), &source);
+        let path = Symbols::path_for(&output);
+        symbols.save(&path)?;
+        println!("wrote {} labels to {}", program.labels.len(), path.display());
+    }
+    Ok(())
+}
+
+// path of `source` as seen from the directory `output` is written to, falling back to an absolute path
+fn relative_to(source: &Path, output: &Path) -> PathBuf {
+    let source = fs::canonicalize(source).unwrap_or_else(|_| source.to_path_buf());
+    let directory = output.parent()
+        .and_then(|directory| fs::canonicalize(if directory.as_os_str().is_empty() { ".".as_ref() } else { directory }).ok());
+    match directory.and_then(|directory| source.strip_prefix(directory).ok().map(PathBuf::from)) {
+        Some(relative) => relative,
+        None => source,
+    }
+}
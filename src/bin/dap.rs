@@ -0,0 +1,43 @@
+use std::io;
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread;
+
+use chipper8::dap::{self, DapServer};
+use chipper8::Result;
+
+// Debug Adapter Protocol server on stdin/stdout, for editors to launch as a debug adapter
+fn main() -> Result<()> {
+    // requests are read on their own thread so the machine can run while waiting for them
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut stdin = io::stdin().lock();
+        while let Ok(Some(message)) = dap::read_message(&mut stdin) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+    let mut server = DapServer::new(io::stdout());
+    while !server.is_terminated() {
+        let request = if server.is_running() {
+            match receiver.try_recv() {
+                Ok(request) => Some(request),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => break,
+            }
+        } else {
+            match receiver.recv() {
+                Ok(request) => Some(request),
+                Err(_) => break,
+            }
+        };
+        if let Some(request) = request {
+            server.handle(&request)?;
+        }
+        if server.is_running() {
+            server.run_frames()?;
+            thread::sleep(server.until_next_frame());
+        }
+    }
+    Ok(())
+}
@@ -1,13 +1,8 @@
-use std::fs;
-use std::path::PathBuf;
-
 use eframe::NativeOptions;
-use egui::{Context, Vec2};
+use egui::Vec2;
 
-use chipper8::{Error, Result};
-use chipper8::command::{Command, MachineState, MetaCommand};
-use chipper8::machine::Machine;
-use chipper8::ui::{MemoryTag, Rom, State, Ui};
+use chipper8::Result;
+use chipper8::app::ReplApp;
 
 fn main() -> Result<()> {
     let mut native_options = NativeOptions::default();
@@ -17,137 +12,3 @@ fn main() -> Result<()> {
                        Box::new(|cc| Box::new(ReplApp::new(cc))));
     Ok(())
 }
-
-struct ReplApp {
-    ui: Ui,
-    machine: Machine,
-    last_time: f64,
-    state: State,
-}
-
-impl ReplApp {
-    fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        Self {
-            ui: Ui::new(),
-            machine: Machine::new(),
-            last_time: 0.0,
-            state: State::new(),
-        }
-    }
-
-    fn execute(&mut self, command: &Command) -> Result<()> {
-        match command {
-            Command::Instruction(instruction) => {
-                // user entered a machine instruction at the prompt
-                // so we should suspend the VM main loop if running
-                self.state.running = false;
-                // todo: Machine::execute should also return result
-                self.machine.execute(instruction)?;
-                Ok(())
-            }
-            Command::Meta(meta) => self.execute_meta(meta),
-        }
-    }
-
-    fn execute_meta(&mut self, command: &MetaCommand) -> Result<()> {
-        match command {
-            MetaCommand::Reset(state) => {
-                self.state.running = false;
-                self.machine.reset();
-                if let Some(state) = state {
-                    match state {
-                        MachineState::Demo => self.machine.demo()?,
-                    };
-                };
-            }
-            MetaCommand::LoadRom(name_or_path, address) => {
-                let mut rom = match Rom::from_file(name_or_path) {
-                    Ok(rom) => rom,
-                    Err(_) => {
-                        let mut path = PathBuf::new();
-                        path.push("roms");
-                        path.push(name_or_path);
-                        path.set_extension("rom");
-                        Rom::from_file(path)?
-                    }
-                };
-                self.state.running = false;
-                if let Some(mut rom) = self.state.unload_rom() {
-                    self.machine.unload_rom(&mut rom);
-                }
-                self.machine.load_rom(&mut rom, address.as_ref());
-                self.state.load_rom(rom);
-            }
-            MetaCommand::UnloadRom => {
-                self.state.running = false;
-                if let Some(mut rom) = self.state.unload_rom() {
-                    self.machine.unload_rom(&mut rom);
-                }
-            }
-            MetaCommand::DumpMachine(path) => {
-                fs::write(path, serde_json::to_string_pretty(&self.machine)?)?;
-            }
-            MetaCommand::LoadMachine(name_or_path) => {
-                self.machine = serde_json::from_str(&fs::read_to_string(name_or_path)?)?;
-            }
-            MetaCommand::Tick => {
-                self.state.running = false;
-                self.tick()?;
-            }
-            MetaCommand::Play => {
-                self.state.running = true;
-            }
-            MetaCommand::Pause => {
-                self.state.running = false;
-            }
-            MetaCommand::PlayPause => {
-                self.state.running = !self.state.running;
-            }
-        };
-        Ok(())
-    }
-
-    fn tick(&mut self) -> Result<()> {
-        let instruction = self.machine.next_instruction()?;
-        self.state.command_history.append(&Command::Instruction(instruction), false);
-        self.machine.tick()?;
-        Ok(())
-    }
-}
-
-impl eframe::App for ReplApp {
-    fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
-        self.state.memory_tags.insert(MemoryTag::ProgramCounter, self.machine.program_counter.as_range(2));
-        self.state.memory_tags.insert(MemoryTag::Index, self.machine.index.as_range(1));
-        self.ui.draw(ctx, &self.machine, &mut self.state);
-        self.machine.key_buffer = self.state.key_capture.key();
-        if let Some(command) = &self.state.command_buffer.take() {
-            self.state.command_history.append(command, true);
-            match self.execute(command) {
-                Ok(_) => {}
-                Err(error) => {
-                    self.state.error = Some(error);
-                    self.state.running = false;
-                }
-            };
-        };
-        // if VM main loop is running, and timer is up, execute next command
-        if self.state.running {
-            // todo make timing here configurable
-            if ctx.input().time - self.last_time > self.state.frame_time().as_secs_f64() {
-                self.last_time = ctx.input().time;
-                self.state.error = self.tick().err().take();
-                if let Some(error) = &self.state.error {
-                    self.state.running = false;
-                    if let Error::InvalidOpCode(_) = error {
-                        if self.state.skip_unknown_opcode {
-                            self.machine.program_counter.step();
-                            self.state.running = true;
-                        }
-                    }
-                }
-            }
-            ctx.request_repaint_after(self.state.frame_time());
-        }
-    }
-}
\ No newline at end of file
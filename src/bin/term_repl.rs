@@ -0,0 +1,140 @@
+use std::io::{self, Stdout, Write};
+
+use crossterm::{cursor, event, execute, queue, terminal};
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+use rustyline::Editor;
+use rustyline::error::ReadlineError;
+
+use chipper8::Result;
+use chipper8::repl::Repl;
+use chipper8::settings::Settings;
+use chipper8::terminal::{render_display, render_status, TerminalKeys};
+use chipper8::ui::util::key_capture::KeyBindings;
+
+fn main() -> Result<()> {
+    let mut settings = Settings::load_or_default();
+    let mut repl = Repl::new();
+    repl.state.key_capture.bindings = KeyBindings::load_or_default();
+    repl.restore(&settings);
+    let mut editor = Editor::<()>::new()?;
+    println!("CHIPPER-8 terminal REPL: enter instructions or meta commands (:load ibm, :play, ...).");
+    println!("While running, <ESC> or ^C pauses. ^D quits.");
+    loop {
+        match editor.readline(">>> ") {
+            Ok(line) => {
+                editor.add_history_entry(line.as_str());
+                repl.state.parse_command(&line);
+                repl.execute_buffered();
+                if repl.state.gdb.is_some() {
+                    serve_debugger(&mut repl)?;
+                }
+                if repl.state.running {
+                    run(&mut repl, &mut io::stdout())?;
+                }
+                print_machine(&mut repl);
+                save_settings(&repl, &mut settings);
+            }
+            // ^C clears the current line, like in a shell
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok(())
+}
+
+// writes back settings changed by commands (e.g. loading a ROM)
+fn save_settings(repl: &Repl, settings: &mut Settings) {
+    let current = Settings {
+        open_windows: settings.open_windows.clone(),
+        ..repl.state.settings()
+    };
+    if current != *settings {
+        if let Err(error) = current.save() {
+            println!("Error saving settings: {}", error);
+        }
+        *settings = current;
+    }
+}
+
+fn print_machine(repl: &mut Repl) {
+    for line in render_display(&repl.machine.display) {
+        println!("{}", line);
+    }
+    for line in render_status(&repl.machine, repl.state.symbols()) {
+        println!("{}", line);
+    }
+    if let Some(error) = repl.state.error.take() {
+        println!("Error: {}", error);
+    }
+}
+
+// runs the VM main loop, redrawing the display in place, until the machine stops or the user pauses
+fn run(repl: &mut Repl, stdout: &mut Stdout) -> Result<()> {
+    terminal::enable_raw_mode()?;
+    execute!(stdout, cursor::Hide)?;
+    let result = run_raw(repl, stdout);
+    execute!(stdout, cursor::Show)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+// hands the machine to the debugger started with `:gdb` until the session ends or the user presses <ESC>
+fn serve_debugger(repl: &mut Repl) -> Result<()> {
+    println!("Serving GDB remote protocol: <ESC> stops the server.");
+    terminal::enable_raw_mode()?;
+    let result = serve_debugger_raw(repl);
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn serve_debugger_raw(repl: &mut Repl) -> Result<()> {
+    while repl.state.gdb.is_some() {
+        if event::poll(repl.state.frame_time())? {
+            if let Event::Key(key) = event::read()? {
+                let interrupt = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                if key.code == KeyCode::Esc || interrupt {
+                    repl.state.gdb = None;
+                }
+            }
+            continue;
+        }
+        repl.poll_gdb();
+    }
+    Ok(())
+}
+
+fn run_raw(repl: &mut Repl, stdout: &mut Stdout) -> Result<()> {
+    let mut drawn_lines = 0;
+    let mut keys = TerminalKeys::new();
+    while repl.state.running {
+        if event::poll(repl.state.until_next_frame())? {
+            if let Event::Key(key) = event::read()? {
+                let interrupt = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                if key.code == KeyCode::Esc || interrupt {
+                    repl.state.running = false;
+                } else {
+                    keys.press(&repl.state.key_capture.bindings, key.code);
+                }
+            }
+            continue;
+        }
+        repl.state.key_capture.update_with(&mut keys);
+        repl.machine.key_buffer = repl.state.key_capture.key();
+        repl.run_frames();
+        if drawn_lines > 0 {
+            queue!(stdout, cursor::MoveUp(drawn_lines))?;
+        }
+        let lines = render_display(&repl.machine.display);
+        for line in &lines {
+            queue!(stdout, cursor::MoveToColumn(0))?;
+            write!(stdout, "{}\r\n", line)?;
+        }
+        stdout.flush()?;
+        drawn_lines = lines.len() as u16;
+    }
+    if drawn_lines > 0 {
+        execute!(stdout, cursor::MoveUp(drawn_lines), terminal::Clear(terminal::ClearType::FromCursorDown))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+// std's `Instant` panics in browsers; natively this is the same type
+pub use instant::Instant;
+
+/// display frames per second: instructions are executed in batches of one frame's worth
+pub const FRAME_RATE: u32 = 60;
+pub const FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / FRAME_RATE as u64);
+
+// frames caught up on at most after falling behind (e.g. the window was hidden); beyond that the
+// clock restarts rather than running flat out to make up for lost time
+const MAX_CATCH_UP: u32 = 5;
+
+/// paces emulation in 60Hz frames against a fixed schedule, so time spent executing (or sleeping
+/// too long) doesn't accumulate as drift
+#[derive(Clone, Debug, Default)]
+pub struct FrameClock {
+    // when the next frame is due; the clock starts with the first frame asked for
+    next_frame: Option<Instant>,
+    // fraction of an instruction carried over to the next frame, for speeds that don't divide evenly
+    carry: f64,
+}
+
+impl FrameClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// restarts the schedule, e.g. after pausing, so the time paused isn't caught up on
+    pub fn reset(&mut self) {
+        self.next_frame = None;
+    }
+
+    /// frames due at `now` since the last call
+    pub fn frames_due(&mut self, now: Instant) -> u32 {
+        let Some(next_frame) = self.next_frame else {
+            self.next_frame = Some(now + FRAME_TIME);
+            return 1;
+        };
+        if now < next_frame {
+            return 0;
+        }
+        let frames = 1 + ((now - next_frame).as_nanos() / FRAME_TIME.as_nanos()) as u32;
+        if frames > MAX_CATCH_UP {
+            self.next_frame = Some(now + FRAME_TIME);
+            return MAX_CATCH_UP;
+        }
+        self.next_frame = Some(next_frame + FRAME_TIME * frames);
+        frames
+    }
+
+    /// how long until the next frame is due
+    pub fn until_next_frame(&self, now: Instant) -> Duration {
+        self.next_frame.map_or(Duration::ZERO, |next_frame| next_frame.saturating_duration_since(now))
+    }
+
+    /// instructions to execute in the next frame at `instructions_per_second`
+    pub fn instructions_per_frame(&mut self, instructions_per_second: f64) -> usize {
+        let instructions = instructions_per_second / FRAME_RATE as f64 + self.carry;
+        self.carry = instructions.fract();
+        instructions as usize
+    }
+}
+
+/// a front-end's emulation loop, run in 60Hz frames by `run_frames`
+pub trait FrameRunner {
+    /// why running stopped before all frames due were run
+    type Stop;
+
+    fn clock(&mut self) -> &mut FrameClock;
+
+    /// executes one frame: the frame's instructions at the configured speed, and the timers
+    fn run_frame(&mut self) -> ControlFlow<Self::Stop>;
+}
+
+/// runs the frames due by the runner's clock, or in turbo mode as many as fit in one frame's time,
+/// stopping early if a frame says so
+pub fn run_frames<R: FrameRunner>(runner: &mut R, turbo: bool) -> ControlFlow<R::Stop> {
+    let started = Instant::now();
+    let frames = if turbo { u32::MAX } else { runner.clock().frames_due(started) };
+    for _ in 0..frames {
+        runner.run_frame()?;
+        if turbo && started.elapsed() >= FRAME_TIME {
+            break;
+        }
+    }
+    ControlFlow::Continue(())
+}
+
+/// blocks for `duration`; browsers can't block, so on the web this returns straight away and
+/// front-ends pace emulation with repaints instead
+pub fn sleep(duration: Duration) {
+    #[cfg(not(target_arch = "wasm32"))]
+    std::thread::sleep(duration);
+    #[cfg(target_arch = "wasm32")]
+    let _ = duration;
+}
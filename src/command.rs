@@ -47,6 +47,43 @@ impl Display for MachineState {
     }
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Profiling {
+    On,
+    Off,
+    Reset,
+}
+
+impl Display for Profiling {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::On => write!(f, "on"),
+            Self::Off => write!(f, "off"),
+            Self::Reset => write!(f, "reset"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Tracing {
+    On,
+    Off,
+    Clear,
+    // also write the trace to a file
+    File(String),
+}
+
+impl Display for Tracing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::On => write!(f, "on"),
+            Self::Off => write!(f, "off"),
+            Self::Clear => write!(f, "clear"),
+            Self::File(path) => write!(f, "{}", path),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum MetaCommand {
     Reset(Option<MachineState>),
@@ -58,6 +95,20 @@ pub enum MetaCommand {
     Play,
     Pause,
     PlayPause,
+    LoadSymbols(String),
+    Break(Location),
+    Unbreak(Location),
+    Tag(Address, Address, String),
+    Untag(String),
+    Profile(Profiling),
+    Coverage(String),
+    Trace(Tracing),
+    Finish,
+    Next,
+    Until(Location),
+    Run(usize),
+    // serve the GDB remote protocol on a port, or stop serving it
+    Gdb(Option<u16>),
 }
 
 impl Display for MetaCommand {
@@ -81,6 +132,37 @@ impl Display for MetaCommand {
             Self::Play => write!(f, ":play"),
             Self::Pause => write!(f, ":pause"),
             Self::PlayPause => write!(f, ":play-pause"),
+            Self::LoadSymbols(path) => write!(f, ":symbols {}", path),
+            Self::Break(location) => write!(f, ":break {}", location),
+            Self::Unbreak(location) => write!(f, ":unbreak {}", location),
+            Self::Tag(start, end, name) => write!(f, ":tag {} {} \"{}\"",
+                                                  ui::util::Address::from(start), ui::util::Address::from(end), name),
+            Self::Untag(name) => write!(f, ":untag \"{}\"", name),
+            Self::Profile(profiling) => write!(f, ":profile {}", profiling),
+            Self::Coverage(path) => write!(f, ":coverage {}", path),
+            Self::Trace(tracing) => write!(f, ":trace {}", tracing),
+            Self::Finish => write!(f, ":finish"),
+            Self::Next => write!(f, ":next"),
+            Self::Until(location) => write!(f, ":until {}", location),
+            Self::Run(ticks) => write!(f, ":run {}", ticks),
+            Self::Gdb(Some(port)) => write!(f, ":gdb {}", port),
+            Self::Gdb(None) => write!(f, ":gdb off"),
+        }
+    }
+}
+
+/// a place in the program given either as an address or as a label from the loaded symbols
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Location {
+    Address(Address),
+    Label(String),
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Address(address) => write!(f, "{}", ui::util::Address::from(address)),
+            Self::Label(label) => write!(f, "{}", label),
         }
     }
-}
\ No newline at end of file
+}
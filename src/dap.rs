@@ -0,0 +1,435 @@
+//! A Debug Adapter Protocol server, so editors with DAP support can launch ROMs and debug them by
+//! source line using the assembler's debug symbols.
+//!
+//! Execution goes through a `Repl`, which already knows about breakpoints and stepping; the server
+//! only translates requests into meta commands and reports where the machine stopped.
+
+use std::collections::BTreeSet;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::{Error, Result};
+use crate::command::{Command, MetaCommand};
+use crate::encoding::base64;
+use crate::library;
+use crate::machine::config::NUM_REGISTERS;
+use crate::repl::Repl;
+use crate::ui::util::{Address, Byte, Register};
+
+// the machine is the only thread
+const THREAD_ID: u64 = 1;
+
+// variable references of the scopes every frame shares
+const REGISTERS: u64 = 1;
+const TIMERS: u64 = 2;
+const STACK: u64 = 3;
+
+/// reads one `Content-Length` framed message, or `None` at the end of the input
+pub fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = Some(value.trim().parse::<usize>()?);
+        }
+    }
+    let length = length.ok_or_else(|| Error::MissingArgument(String::from("message without Content-Length")))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+pub fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+// memory references are addresses, in hex as the debugger shows them
+fn parse_memory_reference(reference: &str) -> Option<usize> {
+    match reference.strip_prefix("0x").or_else(|| reference.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => reference.parse().ok(),
+    }
+}
+
+fn variable(name: impl Into<String>, value: impl Into<String>) -> Value {
+    json!({ "name": name.into(), "value": value.into(), "variablesReference": 0 })
+}
+
+/// serves one debugging session: requests go to `handle`, and `run` executes the machine while the
+/// debugger has it running. Responses and events are written to `writer`.
+pub struct DapServer<W: Write> {
+    pub repl: Repl,
+    writer: W,
+    sequence: u64,
+    // events to send after the response to the current request
+    events: Vec<Value>,
+    // the source file the loaded ROM was assembled from
+    source: Option<PathBuf>,
+    // breakpoints set by source line, replaced by each `setBreakpoints`
+    source_breakpoints: BTreeSet<u16>,
+    stop_on_entry: bool,
+    // the debugger was told the machine is running, and is owed a stopped event
+    resumed: bool,
+    // reported when the machine stops without hitting a breakpoint or an error
+    stop_reason: &'static str,
+    terminated: bool,
+}
+
+impl<W: Write> DapServer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            repl: Repl::new(),
+            writer,
+            sequence: 0,
+            events: Vec::new(),
+            source: None,
+            source_breakpoints: BTreeSet::new(),
+            stop_on_entry: false,
+            resumed: false,
+            stop_reason: "pause",
+            terminated: false,
+        }
+    }
+
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.repl.state.running
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+
+    /// how long to wait before calling `run_frames` again
+    pub fn until_next_frame(&self) -> Duration {
+        self.repl.state.until_next_frame()
+    }
+
+    /// answers a request, followed by any events it caused
+    pub fn handle(&mut self, request: &Value) -> Result<()> {
+        let command = request["command"].as_str().unwrap_or_default();
+        let response = match self.request(command, &request["arguments"]) {
+            Ok(body) => json!({ "success": true, "body": body }),
+            Err(error) => json!({ "success": false, "message": error.to_string() }),
+        };
+        let mut response = response.as_object().unwrap().clone();
+        response.insert(String::from("type"), json!("response"));
+        response.insert(String::from("request_seq"), request["seq"].clone());
+        response.insert(String::from("command"), json!(command));
+        self.send(Value::Object(response))?;
+        for event in std::mem::take(&mut self.events) {
+            self.send(event)?;
+        }
+        Ok(())
+    }
+
+    /// executes up to `max_ticks` instructions while running, reporting why the machine stopped
+    pub fn run(&mut self, max_ticks: usize) -> Result<()> {
+        for _ in 0..max_ticks {
+            if !self.repl.state.running {
+                break;
+            }
+            self.repl.step_running();
+        }
+        self.report_stop()
+    }
+
+    /// executes the frames due while running, as the REPL's front-ends do, reporting why the
+    /// machine stopped
+    pub fn run_frames(&mut self) -> Result<()> {
+        self.repl.run_frames();
+        self.report_stop()
+    }
+
+    fn report_stop(&mut self) -> Result<()> {
+        if self.resumed && !self.repl.state.running {
+            self.resumed = false;
+            self.stopped();
+        }
+        for event in std::mem::take(&mut self.events) {
+            self.send(event)?;
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, mut message: Value) -> Result<()> {
+        self.sequence += 1;
+        message["seq"] = json!(self.sequence);
+        write_message(&mut self.writer, &message)
+    }
+
+    fn event(&mut self, event: &str, body: Value) {
+        self.events.push(json!({ "type": "event", "event": event, "body": body }));
+    }
+
+    fn stopped_event(&mut self, reason: &str, text: Option<String>) {
+        self.event("stopped", json!({
+            "reason": reason,
+            "threadId": THREAD_ID,
+            "allThreadsStopped": true,
+            "text": text,
+        }));
+    }
+
+    fn exited(&mut self) {
+        self.repl.state.running = false;
+        self.event("exited", json!({ "exitCode": 0 }));
+        self.event("terminated", json!({}));
+    }
+
+    // reports why the machine stopped running
+    fn stopped(&mut self) {
+        match self.repl.state.error.take() {
+            Some(Error::Breakpoint(_)) => self.stopped_event("breakpoint", None),
+            Some(Error::MachineExit) => self.exited(),
+            Some(error) => self.stopped_event("exception", Some(error.to_string())),
+            None => self.stopped_event(self.stop_reason, None),
+        }
+    }
+
+    fn resume(&mut self, reason: &'static str) {
+        self.repl.state.error = None;
+        self.stop_reason = reason;
+        self.resumed = true;
+    }
+
+    fn execute(&mut self, command: MetaCommand) -> Result<()> {
+        self.repl.execute(&Command::Meta(command))
+    }
+
+    // runs a stepping command: those that complete immediately report their stop straight away
+    fn step(&mut self, command: MetaCommand) -> Result<Value> {
+        self.resume("step");
+        match self.execute(command) {
+            Err(Error::MachineExit) => {
+                self.resumed = false;
+                self.exited();
+                return Ok(Value::Null);
+            }
+            result => result?,
+        }
+        if !self.repl.state.running {
+            self.resumed = false;
+            self.stopped();
+        }
+        Ok(Value::Null)
+    }
+
+    fn request(&mut self, command: &str, arguments: &Value) -> Result<Value> {
+        match command {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsReadMemoryRequest": true,
+                "supportsTerminateRequest": true,
+            })),
+            "launch" => self.launch(arguments),
+            "setBreakpoints" => self.set_breakpoints(arguments),
+            "setExceptionBreakpoints" => Ok(json!({ "breakpoints": [] })),
+            "configurationDone" => {
+                if self.stop_on_entry {
+                    self.stopped_event("entry", None);
+                } else {
+                    self.resume("pause");
+                    self.execute(MetaCommand::Play)?;
+                }
+                Ok(Value::Null)
+            }
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "CHIP-8" }] })),
+            "stackTrace" => Ok(self.stack_trace()),
+            "scopes" => Ok(json!({ "scopes": [
+                { "name": "Registers", "variablesReference": REGISTERS, "expensive": false },
+                { "name": "Timers", "variablesReference": TIMERS, "expensive": false },
+                { "name": "Stack", "variablesReference": STACK, "expensive": false },
+            ] })),
+            "variables" => Ok(json!({ "variables": self.variables(arguments["variablesReference"].as_u64()) })),
+            "readMemory" => self.read_memory(arguments),
+            "continue" => {
+                self.resume("pause");
+                self.execute(MetaCommand::Play)?;
+                Ok(json!({ "allThreadsContinued": true }))
+            }
+            "next" => self.step(MetaCommand::Next),
+            "stepIn" => self.step(MetaCommand::Tick),
+            "stepOut" => self.step(MetaCommand::Finish),
+            "pause" => {
+                self.execute(MetaCommand::Pause)?;
+                self.resumed = false;
+                self.stopped_event("pause", None);
+                Ok(Value::Null)
+            }
+            "terminate" => {
+                self.execute(MetaCommand::Pause)?;
+                self.event("terminated", json!({}));
+                Ok(Value::Null)
+            }
+            "disconnect" => {
+                self.execute(MetaCommand::Pause)?;
+                self.terminated = true;
+                Ok(Value::Null)
+            }
+            _ => Err(Error::UnsupportedRequest(String::from(command))),
+        }
+    }
+
+    fn launch(&mut self, arguments: &Value) -> Result<Value> {
+        let program = arguments["program"].as_str()
+            .ok_or_else(|| Error::MissingArgument(String::from("launch requires a program")))?;
+        let path = library::find_rom(program).ok_or_else(|| Error::RomNotFound(String::from(program)))?;
+        self.execute(MetaCommand::LoadRom(path.to_string_lossy().into_owned(), None))?;
+        if let Some(symbols) = arguments["symbols"].as_str() {
+            self.execute(MetaCommand::LoadSymbols(String::from(symbols)))?;
+        }
+        self.source = self.repl.state.symbols().and_then(|symbols| symbols.source.as_ref()).map(|source| {
+            // assembled sources name themselves; symbol maps name the source relative to the map,
+            // which sits next to the ROM
+            let source = match path.parent() {
+                Some(directory) if *source != path => directory.join(source),
+                _ => source.clone(),
+            };
+            source.canonicalize().unwrap_or(source)
+        });
+        self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+        self.event("initialized", json!({}));
+        Ok(Value::Null)
+    }
+
+    fn is_source(&self, path: &Path) -> bool {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.source.as_ref() == Some(&path)
+    }
+
+    // the first address assembled from `line` or, if it produced no code, from a line after it
+    fn address_of_line(&self, line: usize) -> Option<(u16, usize)> {
+        let symbols = self.repl.state.symbols()?;
+        symbols.lines.iter()
+            .filter(|(_, address_line)| **address_line >= line)
+            .min_by_key(|(address, address_line)| (**address_line, **address))
+            .map(|(address, address_line)| (*address, *address_line))
+    }
+
+    fn set_breakpoints(&mut self, arguments: &Value) -> Result<Value> {
+        let path = arguments["source"]["path"].as_str().map(PathBuf::from);
+        let in_source = path.is_some_and(|path| self.is_source(&path));
+        for address in std::mem::take(&mut self.source_breakpoints) {
+            self.repl.state.breakpoints.remove(&address);
+        }
+        let requested = arguments["breakpoints"].as_array().cloned().unwrap_or_default();
+        let mut breakpoints = Vec::new();
+        for breakpoint in requested {
+            let line = breakpoint["line"].as_u64().unwrap_or_default() as usize;
+            match self.address_of_line(line).filter(|_| in_source) {
+                Some((address, line)) => {
+                    self.source_breakpoints.insert(address);
+                    self.repl.state.breakpoints.insert(address);
+                    breakpoints.push(json!({
+                        "verified": true,
+                        "line": line,
+                        "instructionReference": Address::from(address).to_string(),
+                    }));
+                }
+                None => breakpoints.push(json!({
+                    "verified": false,
+                    "line": line,
+                    "message": "no code at or after this line",
+                })),
+            }
+        }
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn source(&self) -> Value {
+        match &self.source {
+            Some(path) => json!({
+                "name": path.file_name().map(|name| name.to_string_lossy()),
+                "path": path,
+            }),
+            None => Value::Null,
+        }
+    }
+
+    fn stack_trace(&self) -> Value {
+        let symbols = self.repl.state.symbols();
+        let label = |address: u16| symbols.and_then(|symbols| symbols.label_at(address))
+            .unwrap_or_else(|| Address::from(address).to_string());
+        let frames: Vec<_> = self.repl.machine.call_frames().iter().enumerate().map(|(id, frame)| {
+            let name = match (frame.entry, frame.return_address) {
+                (Some(entry), _) => label(entry),
+                (None, Some(_)) => String::from("?"),
+                (None, None) => String::from("(program)"),
+            };
+            let line = symbols.and_then(|symbols| symbols.line_at(frame.location));
+            json!({
+                "id": id,
+                "name": name,
+                "source": if line.is_some() { self.source() } else { Value::Null },
+                "line": line.unwrap_or_default(),
+                "column": if line.is_some() { 1 } else { 0 },
+                "instructionPointerReference": Address::from(frame.location).to_string(),
+            })
+        }).collect();
+        json!({ "stackFrames": frames, "totalFrames": frames.len() })
+    }
+
+    fn variables(&self, reference: Option<u64>) -> Vec<Value> {
+        let machine = &self.repl.machine;
+        match reference {
+            Some(REGISTERS) => {
+                let mut variables: Vec<_> = (0..NUM_REGISTERS)
+                    .map(|register| variable(Register::from(register).to_string(), Byte::from(machine.registers[register]).to_string()))
+                    .collect();
+                let index = Address::from(&machine.index).to_string();
+                variables.push(json!({ "name": "I", "value": index, "variablesReference": 0, "memoryReference": index }));
+                variables.push(variable("PC", Address::from(&machine.program_counter).to_string()));
+                variables
+            }
+            Some(TIMERS) => vec![
+                variable("DT", machine.delay_timer.to_string()),
+                variable("ST", machine.sound_timer.to_string()),
+            ],
+            Some(STACK) => {
+                let symbols = self.repl.state.symbols();
+                let mut variables = vec![variable("SP", machine.stack.pointer.to_string())];
+                for (depth, address) in machine.stack.data[..machine.stack.pointer].iter().enumerate() {
+                    let Some(address) = address else { continue; };
+                    let address = u16::from(address);
+                    let label = symbols.and_then(|symbols| symbols.label_at(address))
+                        .map_or(String::new(), |label| format!(" ({})", label));
+                    variables.push(variable(format!("[{}]", depth), format!("{}{}", Address::from(address), label)));
+                }
+                variables
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn read_memory(&self, arguments: &Value) -> Result<Value> {
+        let reference = arguments["memoryReference"].as_str().unwrap_or_default();
+        let start = parse_memory_reference(reference)
+            .ok_or_else(|| Error::MissingArgument(format!("invalid memory reference `{}`", reference)))?;
+        let start = start.saturating_add_signed(arguments["offset"].as_i64().unwrap_or_default() as isize);
+        let count = arguments["count"].as_u64().unwrap_or_default() as usize;
+        let memory = &self.repl.machine.memory;
+        let bytes = memory.get(start.min(memory.len())..start.saturating_add(count).min(memory.len())).unwrap_or_default();
+        Ok(json!({
+            "address": format!("{:#X}", start),
+            "data": base64(bytes),
+            "unreadableBytes": count - bytes.len(),
+        }))
+    }
+}
@@ -0,0 +1,163 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Map, Value};
+
+use crate::Result;
+use crate::encoding::base64;
+use crate::machine::Machine;
+use crate::machine::config::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+/// file formats for dumps of the display
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum ScreenFormat {
+    /// one line per row, `#` for pixels that are on and `.` for those that are off
+    Ascii,
+    /// plain (text) portable bitmap
+    Pbm,
+    /// greyscale PNG image
+    Png,
+    /// one line of 16 hex digits per row, leftmost pixel in the most significant bit
+    Hex,
+}
+
+impl ScreenFormat {
+    /// the format matching a file extension: `txt`, `pbm`, `png` or `hex`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "txt" => Some(Self::Ascii),
+            "pbm" => Some(Self::Pbm),
+            "png" => Some(Self::Png),
+            "hex" => Some(Self::Hex),
+            _ => None,
+        }
+    }
+
+    pub fn encode(&self, display: &[u8]) -> Result<Vec<u8>> {
+        let pixel = |x: usize, y: usize| display.get(x + y * DISPLAY_WIDTH).is_some_and(|p| *p != 0);
+        let mut text = String::new();
+        match self {
+            Self::Ascii => {
+                for y in 0..DISPLAY_HEIGHT {
+                    text.extend((0..DISPLAY_WIDTH).map(|x| if pixel(x, y) { '#' } else { '.' }));
+                    text.push('\n');
+                }
+            }
+            Self::Pbm => {
+                // in bitmaps, 1 is black: pixels that are on are drawn black on white
+                let _ = writeln!(text, "P1\n{} {}", DISPLAY_WIDTH, DISPLAY_HEIGHT);
+                for y in 0..DISPLAY_HEIGHT {
+                    let row: Vec<_> = (0..DISPLAY_WIDTH).map(|x| if pixel(x, y) { "1" } else { "0" }).collect();
+                    let _ = writeln!(text, "{}", row.join(" "));
+                }
+            }
+            Self::Png => return encode_png(display),
+            Self::Hex => {
+                for y in 0..DISPLAY_HEIGHT {
+                    let row = (0..DISPLAY_WIDTH).fold(0u64, |row, x| row << 1 | pixel(x, y) as u64);
+                    let _ = writeln!(text, "{:016x}", row);
+                }
+            }
+        }
+        Ok(text.into_bytes())
+    }
+}
+
+fn encode_png(display: &[u8]) -> Result<Vec<u8>> {
+    let pixels: Vec<u8> = (0..DISPLAY_WIDTH * DISPLAY_HEIGHT)
+        .map(|i| if display.get(i).is_some_and(|p| *p != 0) { 0xFF } else { 0x00 })
+        .collect();
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&pixels)?;
+    writer.finish()?;
+    Ok(bytes)
+}
+
+/// writes the display to a file when a headless run ends, and optionally every few frames
+#[derive(Clone, Debug)]
+pub struct ScreenDump {
+    pub path: PathBuf,
+    pub format: ScreenFormat,
+    // frames between intermediate dumps, which are numbered by frame, e.g. `screen-000060.png`
+    pub every: Option<u64>,
+}
+
+impl ScreenDump {
+    /// a final dump to `path`, in the format its extension suggests (ASCII if it suggests none)
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let format = ScreenFormat::from_path(&path).unwrap_or(ScreenFormat::Ascii);
+        Self { path, format, every: None }
+    }
+
+    /// whether an intermediate dump is due after `frame` frames
+    pub fn is_due(&self, frame: u64) -> bool {
+        self.every.is_some_and(|every| every > 0 && frame.is_multiple_of(every))
+    }
+
+    /// the path of the intermediate dump after `frame` frames
+    pub fn frame_path(&self, frame: u64) -> PathBuf {
+        let stem = self.path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        let mut name = format!("{}-{:06}", stem, frame);
+        if let Some(extension) = self.path.extension() {
+            name.push('.');
+            name.push_str(&extension.to_string_lossy());
+        }
+        self.path.with_file_name(name)
+    }
+
+    pub fn write(&self, path: &Path, display: &[u8]) -> Result<()> {
+        fs::write(path, self.format.encode(display)?)?;
+        Ok(())
+    }
+}
+
+/// the parts of the machine state a dump includes; nothing selected means everything
+#[derive(Clone, Debug, Default)]
+pub struct StateSelection {
+    // registers, index, program counter, stack and timers
+    pub registers: bool,
+    pub memory: Vec<Range<u16>>,
+}
+
+impl StateSelection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.registers && self.memory.is_empty()
+    }
+
+    /// the selected state, with the same field names (and encodings) as the full machine state
+    pub fn dump(&self, machine: &Machine) -> Result<Value> {
+        let full = serde_json::to_value(machine)?;
+        let Value::Object(mut full) = full else { return Ok(full); };
+        if self.is_empty() {
+            return Ok(Value::Object(full));
+        }
+        let mut selected = Map::new();
+        if self.registers {
+            for key in ["registers", "index", "program_counter", "stack", "delay_timer", "sound_timer"] {
+                if let Some(value) = full.remove(key) {
+                    selected.insert(String::from(key), value);
+                }
+            }
+        }
+        if !self.memory.is_empty() {
+            let ranges: Vec<_> = self.memory.iter().map(|range| {
+                let end = (range.end as usize).min(machine.memory.len());
+                let start = (range.start as usize).min(end);
+                json!({ "start": start, "bytes": base64(&machine.memory[start..end]) })
+            }).collect();
+            selected.insert(String::from("memory"), Value::Array(ranges));
+        }
+        Ok(Value::Object(selected))
+    }
+}
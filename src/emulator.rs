@@ -1,83 +1,391 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
 use std::fs;
-use std::path::PathBuf;
-use std::thread;
-use std::time::{Duration, Instant};
+use std::ops::{ControlFlow, Range};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use crate::{Error, Result};
-use crate::machine::Machine;
+use crate::clock::{self, FrameClock, FrameRunner, Instant};
+use crate::dump::{ScreenDump, StateSelection};
+use crate::gdb::GdbServer;
+use crate::library::RomOptions;
+use crate::machine::{Instruction, Machine};
+use crate::machine::instruction::Input;
+use crate::state::{self, RomIdentity};
+use crate::trace::Tracer;
 use crate::ui::Rom;
+use crate::ui::util::Address;
 
 pub struct EmulatorConfig {
-    pub rom_path: PathBuf,
+    // instructions per second at normal speed
     pub fps: u64,
+    // multiplies the instructions executed per frame
+    pub speed: f64,
+    // run as fast as possible instead of in real time
+    pub turbo: bool,
     pub dump_path: Option<PathBuf>,
+    // the parts of the machine state dumped (everything if nothing is selected)
+    pub dump_selection: StateSelection,
+    // writes the display when the run ends, and optionally every few frames
+    pub screen_dump: Option<ScreenDump>,
+    // profiles the run and writes the ROM's coverage when it ends
+    pub coverage_path: Option<PathBuf>,
+    // records executed instructions (must not write to stdout when stdout is used for drawing)
+    pub trace: Option<Tracer>,
+    // step over invalid opcodes instead of terminating
+    pub skip_unknown_opcode: bool,
+    // hands control of the machine to a debugger
+    pub gdb: Option<GdbServer>,
+    // stop after executing this many instructions
+    pub max_ticks: Option<u64>,
+    // stop after running for this long
+    pub max_duration: Option<Duration>,
 }
 
 impl EmulatorConfig {
-    pub fn frame_time(&self) -> Duration {
-        Duration::from_nanos(1_000_000_000 / self.fps)
+    pub fn new() -> Self {
+        Self {
+            fps: 60,
+            speed: 1.0,
+            turbo: false,
+            dump_path: None,
+            dump_selection: StateSelection::new(),
+            screen_dump: None,
+            coverage_path: None,
+            trace: None,
+            skip_unknown_opcode: false,
+            gdb: None,
+            max_ticks: None,
+            max_duration: None,
+        }
+    }
+
+    pub fn instructions_per_second(&self) -> f64 {
+        self.fps as f64 * self.speed
+    }
+}
+
+impl Default for EmulatorConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// why the emulator stopped running (or that it can carry on)
+#[derive(Debug)]
+pub enum RunOutcome {
+    /// everything asked for was executed
+    Running,
+    /// the program exited (or a debugger killed it)
+    Exited,
+    /// the instruction at `address` could not be executed
+    Fault { address: u16, error: Error },
+    /// the program counter reached a breakpoint
+    Breakpoint(u16),
+    /// the next instruction waits for a key press, and no key is pressed
+    WaitingForKey,
+    /// the configured number of instructions was executed
+    TickLimit,
+    /// the emulator ran for the configured time
+    TimeLimit,
+}
+
+impl RunOutcome {
+    /// whether the machine can't (or mustn't) carry on
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Exited | Self::Fault { .. } | Self::TickLimit | Self::TimeLimit)
+    }
+
+    /// process exit code for a headless run ending with this outcome: 0 when the program exited
+    /// or reached a breakpoint, 1 on a fault, 2 when a limit was hit and 3 when it waited for a key
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Running | Self::Exited | Self::Breakpoint(_) => 0,
+            Self::Fault { .. } => 1,
+            Self::TickLimit | Self::TimeLimit => 2,
+            Self::WaitingForKey => 3,
+        }
+    }
+}
+
+impl Display for RunOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Running => write!(f, "running"),
+            Self::Exited => write!(f, "machine exited"),
+            Self::Fault { address, error } => write!(f, "fault at {}: {}", Address::from(*address), error),
+            Self::Breakpoint(address) => write!(f, "stopped at breakpoint {}", Address::from(*address)),
+            Self::WaitingForKey => write!(f, "waiting for a key press"),
+            Self::TickLimit => write!(f, "instruction limit reached"),
+            Self::TimeLimit => write!(f, "time limit reached"),
+        }
+    }
+}
+
+/// things happening inside the emulator that embedders may want to report
+#[derive(Debug)]
+pub enum EmulatorEvent<'a> {
+    /// stepping stopped for a reason other than having executed everything asked for
+    Stopped(&'a RunOutcome),
+    /// an invalid opcode was stepped over, as configured by `skip_unknown_opcode`
+    SkippedOpCode { address: u16, error: &'a Error },
+    /// the final machine state was written to the configured dump path
+    Dumped(&'a Path),
+    /// the final display was written to the configured screen dump path
+    ScreenDumped(&'a Path),
+    /// the ROM's coverage was written to the configured coverage path
+    CoverageWritten(&'a Path),
+}
+
+impl Display for EmulatorEvent<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stopped(outcome) => write!(f, "{}", outcome),
+            Self::SkippedOpCode { address, error } => write!(f, "skipped {} at {}", error, Address::from(*address)),
+            Self::Dumped(path) => write!(f, "wrote final machine state to '{}'", path.display()),
+            Self::ScreenDumped(path) => write!(f, "wrote final display to '{}'", path.display()),
+            Self::CoverageWritten(path) => write!(f, "wrote coverage to '{}'", path.display()),
+        }
     }
 }
 
+/// receives the emulator's events, see `Emulator::on_event`
+pub type EventCallback = Box<dyn FnMut(&EmulatorEvent)>;
+
 pub struct Emulator {
     pub machine: Machine,
-    pub last_time: Instant,
+    pub rom_name: String,
+    // recorded in state dumps
+    pub rom_identity: RomIdentity,
+    // settings the ROM file (or the ROM database) asks for
+    pub rom_options: RomOptions,
+    // where the ROM was loaded, for coverage reports
+    pub rom_range: Range<usize>,
+    pub clock: FrameClock,
     pub terminated: bool,
+    // instructions executed so far
+    pub ticks: u64,
+    // 60Hz frames executed so far
+    pub frames: u64,
+    // when the first instruction was executed
+    started: Option<Instant>,
+    // set by the debugger (or to run until an address), if any
+    pub breakpoints: BTreeSet<u16>,
     pub config: EmulatorConfig,
+    on_event: Option<EventCallback>,
+    // reported once each time the program starts waiting
+    waiting_for_key: bool,
 }
 
 impl Emulator {
-    pub fn new(config: EmulatorConfig) -> Result<Self> {
-        let mut rom = Rom::from_file(&config.rom_path).unwrap();
+    pub fn new(mut rom: Rom, config: EmulatorConfig) -> Self {
         let mut machine = Machine::new();
-        machine.load_rom(&mut rom, None);
-        println!("CHIPPER-8: running ROM '{}'.", rom.name);
-        Ok(Self {
+        // profiling starts with the ROM
+        machine.set_profiling(config.coverage_path.is_some());
+        rom.load(&mut machine, None);
+        Self {
             machine,
-            last_time: Instant::now(),
+            rom_range: rom.loaded_range().unwrap(),
+            rom_identity: RomIdentity::of(&rom),
+            rom_name: rom.name,
+            rom_options: rom.options,
+            clock: FrameClock::new(),
             terminated: false,
+            ticks: 0,
+            frames: 0,
+            started: None,
+            breakpoints: BTreeSet::new(),
             config,
-        })
+            on_event: None,
+            waiting_for_key: false,
+        }
+    }
+
+    /// loads a ROM in any of the formats `Rom::from_file` understands
+    pub fn from_file(path: impl AsRef<Path>, config: EmulatorConfig) -> Result<Self> {
+        Ok(Self::new(Rom::from_file(path)?, config))
+    }
+
+    /// loads a binary CHIP-8 ROM
+    pub fn from_bytes(name: impl Into<String>, bytes: Vec<u8>, config: EmulatorConfig) -> Self {
+        Self::new(Rom::from_bytes(name, bytes), config)
     }
 
+    /// calls `callback` with every event from now on, replacing any previous callback
+    pub fn on_event(&mut self, callback: impl FnMut(&EmulatorEvent) + 'static) {
+        self.on_event = Some(Box::new(callback));
+    }
+
+    fn emit(&mut self, event: &EmulatorEvent) {
+        if let Some(callback) = &mut self.on_event {
+            callback(event);
+        }
+    }
+
+    /// executes one instruction (or, with a debugger attached, lets it do so)
+    pub fn step(&mut self) -> RunOutcome {
+        let outcome = self.execute_instruction();
+        match outcome {
+            RunOutcome::Running => self.waiting_for_key = false,
+            RunOutcome::WaitingForKey if self.waiting_for_key => {}
+            _ => {
+                self.waiting_for_key = matches!(outcome, RunOutcome::WaitingForKey);
+                self.emit(&EmulatorEvent::Stopped(&outcome));
+            }
+        }
+        outcome
+    }
+
+    /// executes up to `count` instructions, stopping early for anything but `RunOutcome::Running`
+    pub fn step_instructions(&mut self, count: usize) -> RunOutcome {
+        for _ in 0..count {
+            let outcome = self.step();
+            if !matches!(outcome, RunOutcome::Running) {
+                return outcome;
+            }
+        }
+        RunOutcome::Running
+    }
+
+    /// executes the instructions of one 60Hz frame at the configured speed, and counts the timers
+    /// down
+    pub fn step_frame(&mut self) -> RunOutcome {
+        let instructions = self.clock.instructions_per_frame(self.config.instructions_per_second());
+        self.frames += 1;
+        self.machine.tick_timers();
+        self.step_instructions(instructions)
+    }
+
+    /// executes the frames due by the clock, or in turbo mode as many as fit in one frame's time
+    pub fn run_frames(&mut self) -> RunOutcome {
+        match clock::run_frames(self, self.config.turbo) {
+            ControlFlow::Continue(()) => RunOutcome::Running,
+            ControlFlow::Break(outcome) => outcome,
+        }
+    }
+
+    /// how long front-ends can wait before calling `tick` again
+    pub fn until_next_frame(&self) -> Duration {
+        if self.config.turbo {
+            return Duration::ZERO;
+        }
+        self.clock.until_next_frame(Instant::now())
+    }
+
+    // the configured limits, checked before each instruction
+    fn limit_reached(&mut self) -> Option<RunOutcome> {
+        if self.config.max_ticks.is_some_and(|max_ticks| self.ticks >= max_ticks) {
+            return Some(RunOutcome::TickLimit);
+        }
+        let started = *self.started.get_or_insert_with(Instant::now);
+        if self.config.max_duration.is_some_and(|max_duration| started.elapsed() >= max_duration) {
+            return Some(RunOutcome::TimeLimit);
+        }
+        None
+    }
+
+    fn execute_instruction(&mut self) -> RunOutcome {
+        if let Some(outcome) = self.limit_reached() {
+            return outcome;
+        }
+        let address = u16::from(&self.machine.program_counter);
+        if let Some(gdb) = &mut self.config.gdb {
+            return match gdb.poll(&mut self.machine, &mut self.breakpoints, 1) {
+                Ok(true) => RunOutcome::Running,
+                Ok(false) => RunOutcome::Exited,
+                Err(error) => RunOutcome::Fault { address, error },
+            };
+        }
+        let result = match &mut self.config.trace {
+            Some(tracer) => tracer.tick(&mut self.machine),
+            None => self.machine.next_instruction().and_then(|_| self.machine.tick()).map_err(Error::from),
+        };
+        self.ticks += 1;
+        match result {
+            Ok(_) => {}
+            Err(Error::MachineExit) => return RunOutcome::Exited,
+            Err(error @ Error::InvalidOpCode(_)) if self.config.skip_unknown_opcode => {
+                self.emit(&EmulatorEvent::SkippedOpCode { address, error: &error });
+                self.machine.program_counter.step();
+            }
+            Err(error) => return RunOutcome::Fault { address, error },
+        }
+        let program_counter = u16::from(&self.machine.program_counter);
+        if self.breakpoints.contains(&program_counter) {
+            return RunOutcome::Breakpoint(program_counter);
+        }
+        let awaiting_key = matches!(self.machine.next_instruction(), Ok(Instruction::Input(Input::Await { .. })));
+        if awaiting_key && self.machine.key_buffer.is_none() {
+            return RunOutcome::WaitingForKey;
+        }
+        RunOutcome::Running
+    }
+
+    /// runs the frames due, for front-ends that call this whenever they redraw; the emulator
+    /// terminates if the machine can't carry on
     pub fn tick(&mut self) {
-        if self.terminated {
-            return;
-        }
-        let current_time = Instant::now();
-        if current_time - self.last_time > self.config.frame_time() {
-            self.last_time = current_time;
-            match self.machine.next_instruction() {
-                Ok(instruction) => {
-                    println!("Executing: {}", instruction);
-                    match self.machine.tick() {
-                        Err(Error::MachineExit) => { self.terminated = true; }
-                        Ok(_) => {}
-                        Err(error) => {
-                            eprintln!("Error: {:?}", error);
-                            panic!("");
-                        }
-                    }
+        if !self.terminated && self.run_frames().is_terminal() {
+            self.terminated = true;
+        }
+    }
+
+    /// runs until the machine stops (for any reason, as nobody can press keys here), then writes
+    /// the configured dumps
+    pub fn run(&mut self) -> Result<RunOutcome> {
+        // frame by frame rather than through `run_frames`, for the intermediate screen dumps
+        let outcome = 'run: loop {
+            let frames = if self.config.turbo { 1 } else { self.clock.frames_due(Instant::now()) };
+            for _ in 0..frames {
+                let outcome = self.step_frame();
+                if let Some(screen_dump) = self.config.screen_dump.as_ref().filter(|dump| dump.is_due(self.frames)) {
+                    screen_dump.write(&screen_dump.frame_path(self.frames), &self.machine.display)?;
                 }
-                Err(error @ Error::InvalidOpCode(_)) => {
-                    eprintln!("Error: {:?}", error);
-                    self.terminated = true;
+                if !matches!(outcome, RunOutcome::Running) {
+                    break 'run outcome;
                 }
-                _ => {}
             }
+            if !self.config.turbo {
+                clock::sleep(self.until_next_frame());
+            }
+        };
+        self.terminated = true;
+        if let Some(tracer) = &mut self.config.trace {
+            tracer.flush()?;
         }
+        if let Some(dump) = self.config.dump_path.clone() {
+            let selection = &self.config.dump_selection;
+            if selection.is_empty() {
+                state::save(&dump, &self.machine, Some(&self.rom_identity))?;
+            } else {
+                fs::write(&dump, serde_json::to_string(&selection.dump(&self.machine)?)?)?;
+            }
+            self.emit(&EmulatorEvent::Dumped(&dump));
+        };
+        if let Some(screen_dump) = self.config.screen_dump.clone() {
+            screen_dump.write(&screen_dump.path, &self.machine.display)?;
+            self.emit(&EmulatorEvent::ScreenDumped(&screen_dump.path));
+        }
+        if let (Some(path), Some(profiler)) = (self.config.coverage_path.clone(), &self.machine.profiler) {
+            let coverage = profiler.coverage(self.rom_range.clone());
+            fs::write(&path, serde_json::to_string_pretty(&coverage)?)?;
+            self.emit(&EmulatorEvent::CoverageWritten(&path));
+        }
+        Ok(outcome)
     }
+}
+
+impl FrameRunner for Emulator {
+    type Stop = RunOutcome;
 
-    pub fn run(&mut self) -> Result<()> {
-        while !self.terminated {
-            self.tick();
-            thread::sleep(self.config.frame_time());
+    fn clock(&mut self) -> &mut FrameClock {
+        &mut self.clock
+    }
+
+    fn run_frame(&mut self) -> ControlFlow<RunOutcome> {
+        match self.step_frame() {
+            RunOutcome::Running => ControlFlow::Continue(()),
+            outcome => ControlFlow::Break(outcome),
         }
-        if let Some(dump) = &self.config.dump_path {
-            eprintln!("Writing final machine state to '{}'", dump.display());
-            fs::write(dump, serde_json::to_string(&self.machine)?)?;
-        };
-        Ok(())
     }
 }
-
@@ -0,0 +1,11 @@
+pub use chipper8_core::encoding::{base64, base64_bytes, hex, hex_pixels, pack_pixels, unpack_pixels};
+
+use crate::{Error, Result};
+
+pub fn from_base64(text: &str) -> Result<Vec<u8>> {
+    chipper8_core::encoding::from_base64(text).ok_or_else(|| Error::SyntaxError(String::from("invalid base64")))
+}
+
+pub fn from_hex(text: &str) -> Result<Vec<u8>> {
+    chipper8_core::encoding::from_hex(text).ok_or_else(|| Error::SyntaxError(String::from("invalid hex string")))
+}
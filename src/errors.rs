@@ -19,15 +19,55 @@ pub enum Error {
     OpCodeSyntaxError(String),
     #[error("assembler error: no opcode for `{0}`")]
     NoOpcodeError(Instruction),
+    #[error("assembly error on line {line}: {message}")]
+    AssemblyError { line: usize, message: String },
     #[error("invalid opcode: {0}")]
     InvalidOpCode(OpCode),
+    #[error("missing argument: {0}")]
+    MissingArgument(String),
+    #[error("ROM not found: {0}")]
+    RomNotFound(String),
+    #[error("unsupported or invalid ROM: {0}")]
+    RomFormatError(String),
+    #[error("not in a subroutine")]
+    NotInSubroutine,
+    #[error("unknown label: {0}")]
+    UnknownLabel(String),
+    #[error("unsupported request: {0}")]
+    UnsupportedRequest(String),
     #[error("I/O error: {0}")]
     IoError(#[from] io::Error),
     // todo: move this into a separate error enum inside the machine module
     #[error("normal machine exit")]
     MachineExit,
+    #[error("stopped at breakpoint {0}")]
+    Breakpoint(String),
     #[error("JSON (de-)serialization error: {0}")]
     JsonSerdeError(#[from] serde_json::Error),
+    #[error("invalid save state: {0}")]
+    StateFormatError(String),
+    #[error("warning: state was saved with a different ROM ({0})")]
+    StateRomMismatch(String),
+    #[error("warning: {0}")]
+    Warning(String),
+    #[error("PNG encoding error: {0}")]
+    PngError(#[from] png::EncodingError),
+    #[error("gamepad error: {0}")]
+    GamepadError(String),
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("line editor error: {0}")]
+    ReadlineError(#[from] rustyline::error::ReadlineError),
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
\ No newline at end of file
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl From<chipper8_core::Error> for Error {
+    fn from(error: chipper8_core::Error) -> Self {
+        match error {
+            chipper8_core::Error::IntSizeError(kind, value) => Self::IntSizeError(kind, value),
+            chipper8_core::Error::NoOpcodeError(instruction) => Self::NoOpcodeError(instruction),
+            chipper8_core::Error::InvalidOpCode(op_code) => Self::InvalidOpCode(op_code),
+            chipper8_core::Error::MachineExit => Self::MachineExit,
+        }
+    }
+}
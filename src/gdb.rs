@@ -0,0 +1,438 @@
+//! A minimal GDB remote serial protocol server, so the machine can be inspected and stepped from a
+//! debugger (or anything else speaking RSP) over TCP on localhost.
+//!
+//! The server never blocks: callers `poll` it from their own main loop, which keeps it usable from
+//! both the headless emulator and the REPL front-ends.
+
+use std::collections::BTreeSet;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+
+use crate::{Error, Result};
+use crate::machine::{Address, Machine};
+use crate::machine::config::{MEMORY_SIZE, NUM_REGISTERS, STACK_SIZE};
+
+// register numbers after V0-VF, which are 0-15
+const REGISTER_I: usize = NUM_REGISTERS;
+const REGISTER_PC: usize = NUM_REGISTERS + 1;
+const REGISTER_SP: usize = NUM_REGISTERS + 2;
+const REGISTER_DT: usize = NUM_REGISTERS + 3;
+const REGISTER_ST: usize = NUM_REGISTERS + 4;
+const REGISTER_COUNT: usize = NUM_REGISTERS + 5;
+
+const INTERRUPT: u8 = 0x03;
+
+// stop replies, by the signal a real target would report
+const STOPPED_TRAP: &str = "S05";
+const STOPPED_INTERRUPT: &str = "S02";
+const STOPPED_ILLEGAL_INSTRUCTION: &str = "S04";
+const EXITED: &str = "W00";
+
+const ERROR: &str = "E01";
+
+/// target description sent to the debugger: V0-VF, I, PC, SP and the timers
+pub fn target_description() -> String {
+    let mut registers = String::new();
+    for register in 0..NUM_REGISTERS {
+        registers.push_str(&format!("    <reg name=\"v{:x}\" bitsize=\"8\" regnum=\"{}\" type=\"uint8\"/>\n", register, register));
+    }
+    for (name, regnum, bitsize, kind) in [
+        ("i", REGISTER_I, 16, "data_ptr"),
+        ("pc", REGISTER_PC, 16, "code_ptr"),
+        ("sp", REGISTER_SP, 8, "uint8"),
+        ("dt", REGISTER_DT, 8, "uint8"),
+        ("st", REGISTER_ST, 8, "uint8"),
+    ] {
+        registers.push_str(&format!("    <reg name=\"{}\" bitsize=\"{}\" regnum=\"{}\" type=\"{}\"/>\n",
+                                    name, bitsize, regnum, kind));
+    }
+    format!("<?xml version=\"1.0\"?>\n\
+             <!DOCTYPE target SYSTEM \"gdb-target.dtd\">\n\
+             <target version=\"1.0\">\n  <feature name=\"org.chipper8.core\">\n{}  </feature>\n</target>\n",
+            registers)
+}
+
+// what arrived from the debugger
+enum Received {
+    Packet(String),
+    Interrupt,
+    // checksum mismatch: the debugger will resend
+    Corrupt,
+}
+
+struct Connection {
+    stream: TcpStream,
+    input: Vec<u8>,
+}
+
+impl Connection {
+    // reads whatever is available; false if the debugger hung up
+    fn receive(&mut self) -> Result<bool> {
+        let mut buffer = [0; 1024];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Ok(false),
+                Ok(count) => self.input.extend_from_slice(&buffer[..count]),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => return Ok(true),
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    fn next(&mut self) -> Option<Received> {
+        loop {
+            match *self.input.first()? {
+                INTERRUPT => {
+                    self.input.remove(0);
+                    return Some(Received::Interrupt);
+                }
+                b'$' => {
+                    let end = self.input.iter().position(|byte| *byte == b'#')?;
+                    if self.input.len() < end + 3 {
+                        return None;
+                    }
+                    let raw: Vec<u8> = self.input.drain(..end + 3).collect();
+                    let data = &raw[1..end];
+                    let checksum = std::str::from_utf8(&raw[end + 1..]).ok()
+                        .and_then(|checksum| u8::from_str_radix(checksum, 16).ok());
+                    if checksum != Some(checksum_of(data)) {
+                        return Some(Received::Corrupt);
+                    }
+                    return Some(Received::Packet(String::from_utf8_lossy(&unescape(data)).into_owned()));
+                }
+                // acknowledgements (we never resend) and line noise
+                _ => {
+                    self.input.remove(0);
+                }
+            }
+        }
+    }
+
+    fn send(&mut self, data: &str) -> Result<()> {
+        let data = escape(data.as_bytes());
+        let mut packet = Vec::with_capacity(data.len() + 4);
+        packet.push(b'$');
+        packet.extend_from_slice(&data);
+        packet.extend_from_slice(format!("#{:02x}", checksum_of(&data)).as_bytes());
+        self.stream.write_all(&packet)?;
+        Ok(())
+    }
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+}
+
+fn escape(data: &[u8]) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(data.len());
+    for byte in data {
+        if matches!(byte, b'$' | b'#' | b'}' | b'*') {
+            escaped.extend_from_slice(&[b'}', byte ^ 0x20]);
+        } else {
+            escaped.push(*byte);
+        }
+    }
+    escaped
+}
+
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut unescaped = Vec::with_capacity(data.len());
+    let mut bytes = data.iter();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'}' => unescaped.extend(bytes.next().map(|byte| byte ^ 0x20)),
+            _ => unescaped.push(*byte),
+        }
+    }
+    unescaped
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2)
+        .map(|i| text.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect()
+}
+
+fn parse_hex(text: &str) -> Option<usize> {
+    usize::from_str_radix(text, 16).ok()
+}
+
+// `addr,length` as used by memory and breakpoint packets
+fn parse_range(text: &str) -> Option<(usize, usize)> {
+    let (address, length) = text.split_once(',')?;
+    Some((parse_hex(address)?, parse_hex(length)?))
+}
+
+// little endian, as the debugger expects for multi-byte registers
+fn register_bytes(machine: &Machine, register: usize) -> Option<Vec<u8>> {
+    Some(match register {
+        0..=15 => vec![machine.registers[register]],
+        REGISTER_I => u16::from(&machine.index).to_le_bytes().to_vec(),
+        REGISTER_PC => u16::from(&machine.program_counter).to_le_bytes().to_vec(),
+        REGISTER_SP => vec![machine.stack.pointer as u8],
+        REGISTER_DT => vec![machine.delay_timer],
+        REGISTER_ST => vec![machine.sound_timer],
+        _ => return None,
+    })
+}
+
+fn register_size(register: usize) -> usize {
+    match register {
+        REGISTER_I | REGISTER_PC => 2,
+        _ => 1,
+    }
+}
+
+fn set_register(machine: &mut Machine, register: usize, bytes: &[u8]) -> Option<()> {
+    let word = || Some(u16::from_le_bytes(bytes.try_into().ok()?));
+    let byte = || bytes.first().copied().filter(|_| bytes.len() == 1);
+    match register {
+        0..=15 => machine.registers[register] = byte()?,
+        REGISTER_I => machine.index = Address::try_from(word()?).ok()?,
+        REGISTER_PC => machine.program_counter = Address::try_from(word()?).ok()?,
+        REGISTER_SP => {
+            // only frames that hold a return address can be exposed again
+            let pointer = byte()? as usize;
+            if pointer > STACK_SIZE || machine.stack.data[..pointer].iter().any(Option::is_none) {
+                return None;
+            }
+            machine.stack.pointer = pointer;
+        }
+        REGISTER_DT => machine.delay_timer = byte()?,
+        REGISTER_ST => machine.sound_timer = byte()?,
+        _ => return None,
+    }
+    Some(())
+}
+
+// runs one instruction, returning the stop reply if the machine can't carry on
+fn step(machine: &mut Machine) -> Result<Option<&'static str>> {
+    match machine.next_instruction().and_then(|_| machine.tick()).map_err(Error::from) {
+        Ok(()) => Ok(None),
+        Err(Error::MachineExit) => Ok(Some(EXITED)),
+        Err(Error::InvalidOpCode(_)) => Ok(Some(STOPPED_ILLEGAL_INSTRUCTION)),
+        Err(error) => Err(error),
+    }
+}
+
+/// GDB remote serial protocol server for one debugger connection at a time.
+///
+/// The machine waits for the debugger before running; once the debugger detaches (or hangs up), it
+/// runs freely until another one connects.
+pub struct GdbServer {
+    listener: TcpListener,
+    connection: Option<Connection>,
+    // the debugger continued the target, or nobody is debugging it anymore
+    running: bool,
+}
+
+impl GdbServer {
+    /// listens on `port` on localhost (0 picks a free port)
+    pub fn bind(port: u16) -> Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            connection: None,
+            running: false,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// accepts a debugger, answers the packets received so far and, while the target is running,
+    /// executes up to `max_ticks` instructions; never blocks.
+    ///
+    /// Returns false once the target is gone: killed by the debugger, or the machine exited.
+    pub fn poll(&mut self, machine: &mut Machine, breakpoints: &mut BTreeSet<u16>, max_ticks: usize) -> Result<bool> {
+        if self.connection.is_none() {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(true)?;
+                    stream.set_nodelay(true)?;
+                    self.connection = Some(Connection { stream, input: Vec::new() });
+                    self.running = false;
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+        if let Some(connection) = &mut self.connection {
+            if !connection.receive()? {
+                self.connection = None;
+                self.running = true;
+            }
+        }
+        while let Some(received) = self.connection.as_mut().and_then(Connection::next) {
+            if !self.handle(received, machine, breakpoints)? {
+                return Ok(false);
+            }
+        }
+        if self.running {
+            return self.run(machine, breakpoints, max_ticks);
+        }
+        Ok(true)
+    }
+
+    fn run(&mut self, machine: &mut Machine, breakpoints: &BTreeSet<u16>, max_ticks: usize) -> Result<bool> {
+        for _ in 0..max_ticks {
+            let reply = match step(machine)? {
+                Some(reply) => reply,
+                // breakpoints only apply while someone is there to be told about them
+                None if self.connection.is_some()
+                    && breakpoints.contains(&u16::from(&machine.program_counter)) => STOPPED_TRAP,
+                None => continue,
+            };
+            self.running = false;
+            if let Some(connection) = &mut self.connection {
+                connection.send(reply)?;
+            }
+            return Ok(reply != EXITED);
+        }
+        Ok(true)
+    }
+
+    // false if the debugger killed the target or it exited
+    fn handle(&mut self, received: Received, machine: &mut Machine, breakpoints: &mut BTreeSet<u16>) -> Result<bool> {
+        let Some(connection) = &mut self.connection else { return Ok(true); };
+        let packet = match received {
+            Received::Packet(packet) => packet,
+            Received::Interrupt => {
+                self.running = false;
+                connection.send(STOPPED_INTERRUPT)?;
+                return Ok(true);
+            }
+            Received::Corrupt => {
+                connection.stream.write_all(b"-")?;
+                return Ok(true);
+            }
+        };
+        connection.stream.write_all(b"+")?;
+        let (command, arguments) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+        let reply = match command {
+            "?" => String::from(STOPPED_TRAP),
+            "q" => query(arguments),
+            "H" | "T" => String::from("OK"),
+            "g" => (0..REGISTER_COUNT).filter_map(|register| register_bytes(machine, register))
+                .map(|bytes| to_hex(&bytes))
+                .collect(),
+            "G" => match from_hex(arguments) {
+                Some(bytes) if bytes.len() == (0..REGISTER_COUNT).map(register_size).sum::<usize>() => {
+                    let mut offset = 0;
+                    let mut valid = true;
+                    for register in 0..REGISTER_COUNT {
+                        let size = register_size(register);
+                        valid &= set_register(machine, register, &bytes[offset..offset + size]).is_some();
+                        offset += size;
+                    }
+                    String::from(if valid { "OK" } else { ERROR })
+                }
+                _ => String::from(ERROR),
+            },
+            "p" => parse_hex(arguments).and_then(|register| register_bytes(machine, register))
+                .map_or(String::from(ERROR), |bytes| to_hex(&bytes)),
+            "P" => arguments.split_once('=')
+                .and_then(|(register, value)| set_register(machine, parse_hex(register)?, &from_hex(value)?))
+                .map_or(String::from(ERROR), |_| String::from("OK")),
+            "m" => parse_range(arguments)
+                .and_then(|(address, length)| machine.memory.get(address..address.checked_add(length)?))
+                .map_or(String::from(ERROR), to_hex),
+            "M" => arguments.split_once(':')
+                .and_then(|(range, data)| {
+                    let (address, length) = parse_range(range)?;
+                    let data = from_hex(data).filter(|data| data.len() == length)?;
+                    machine.memory.get_mut(address..address.checked_add(length)?)?.copy_from_slice(&data);
+                    Some(())
+                })
+                .map_or(String::from(ERROR), |_| String::from("OK")),
+            "Z" | "z" => match arguments.split_once(',') {
+                // software and hardware breakpoints are the same thing here
+                Some(("0" | "1", range)) => match parse_range(range).filter(|(address, _)| *address < MEMORY_SIZE) {
+                    Some((address, _)) => {
+                        if command == "Z" {
+                            breakpoints.insert(address as u16);
+                        } else {
+                            breakpoints.remove(&(address as u16));
+                        }
+                        String::from("OK")
+                    }
+                    None => String::from(ERROR),
+                },
+                // watchpoints are not supported
+                _ => String::new(),
+            },
+            "c" | "s" => {
+                if !arguments.is_empty() {
+                    let address = parse_hex(arguments).and_then(|address| Address::try_from(address).ok());
+                    match address {
+                        Some(address) => machine.program_counter = address,
+                        None => {
+                            connection.send(ERROR)?;
+                            return Ok(true);
+                        }
+                    }
+                }
+                if command == "c" {
+                    // the stop reply comes once the target stops
+                    self.running = true;
+                    return Ok(true);
+                }
+                let reply = step(machine)?.unwrap_or(STOPPED_TRAP);
+                connection.send(reply)?;
+                return Ok(reply != EXITED);
+            }
+            "D" => {
+                connection.send("OK")?;
+                self.connection = None;
+                self.running = true;
+                return Ok(true);
+            }
+            "k" => {
+                self.connection = None;
+                return Ok(false);
+            }
+            _ => String::new(),
+        };
+        connection.send(&reply)?;
+        Ok(true)
+    }
+}
+
+// `q` packets: capabilities, the target description and the single thread
+fn query(query: &str) -> String {
+    if query.starts_with("Supported") {
+        return String::from("PacketSize=1000;qXfer:features:read+");
+    }
+    if let Some(request) = query.strip_prefix("Xfer:features:read:") {
+        let Some(("target.xml", range)) = request.split_once(':') else { return String::from("E00"); };
+        let Some((offset, length)) = parse_range(range) else { return String::from(ERROR); };
+        let description = target_description();
+        let chunk = description.get(offset.min(description.len())..).unwrap_or_default();
+        return if chunk.len() <= length {
+            format!("l{}", chunk)
+        } else {
+            format!("m{}", &chunk[..length])
+        };
+    }
+    match query {
+        "Attached" => String::from("1"),
+        "C" => String::from("QC1"),
+        "fThreadInfo" => String::from("m1"),
+        "sThreadInfo" => String::from("l"),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,53 @@
+use gilrs::Gilrs;
+
+use crate::{Error, Result};
+use crate::ui::util::key_capture::KeyBindings;
+
+use super::{Button, InputSource};
+
+impl From<Button> for gilrs::Button {
+    fn from(button: Button) -> Self {
+        match button {
+            Button::South => Self::South,
+            Button::East => Self::East,
+            Button::North => Self::North,
+            Button::West => Self::West,
+            Button::LeftTrigger => Self::LeftTrigger,
+            Button::LeftTrigger2 => Self::LeftTrigger2,
+            Button::RightTrigger => Self::RightTrigger,
+            Button::RightTrigger2 => Self::RightTrigger2,
+            Button::Select => Self::Select,
+            Button::Start => Self::Start,
+            Button::DPadUp => Self::DPadUp,
+            Button::DPadDown => Self::DPadDown,
+            Button::DPadLeft => Self::DPadLeft,
+            Button::DPadRight => Self::DPadRight,
+        }
+    }
+}
+
+/// reads all connected game controllers through the button map in the key bindings
+pub struct GamepadInput {
+    gilrs: Gilrs,
+}
+
+impl GamepadInput {
+    pub fn new() -> Result<Self> {
+        let gilrs = Gilrs::new().map_err(|error| Error::GamepadError(format!("{}", error)))?;
+        Ok(Self { gilrs })
+    }
+}
+
+impl InputSource for GamepadInput {
+    fn poll(&mut self, bindings: &KeyBindings, keys: &mut [bool; 16]) {
+        // drain the event queue so gilrs updates its cached gamepad state
+        while self.gilrs.next_event().is_some() {}
+        for (_id, gamepad) in self.gilrs.gamepads() {
+            for (button, key) in bindings.button_map() {
+                if gamepad.is_pressed(gilrs::Button::from(*button)) {
+                    keys[(*key & 0xF) as usize] = true;
+                }
+            }
+        }
+    }
+}
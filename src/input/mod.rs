@@ -0,0 +1,68 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "gamepad")]
+pub use gamepad::GamepadInput;
+pub use scripted::ScriptedInput;
+
+use crate::ui::util::key_capture::KeyBindings;
+
+#[cfg(feature = "gamepad")]
+mod gamepad;
+mod scripted;
+
+/// something other than the egui keyboard that can hold down keys on the 16-key keypad
+pub trait InputSource {
+    /// marks the keys (indexed by CHIP-8 key value) this source currently holds down; keys held by
+    /// other sources must be left untouched
+    fn poll(&mut self, bindings: &KeyBindings, keys: &mut [bool; 16]);
+}
+
+// gamepad buttons are named here rather than using the gamepad library's types so that mappings
+// can be configured even when built without the optional gamepad feature
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Button {
+    South,
+    East,
+    North,
+    West,
+    LeftTrigger,
+    LeftTrigger2,
+    RightTrigger,
+    RightTrigger2,
+    Select,
+    Start,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl Button {
+    pub const ALL: [Button; 14] = [
+        Self::DPadUp, Self::DPadDown, Self::DPadLeft, Self::DPadRight,
+        Self::South, Self::East, Self::North, Self::West,
+        Self::LeftTrigger, Self::LeftTrigger2, Self::RightTrigger, Self::RightTrigger2,
+        Self::Select, Self::Start,
+    ];
+}
+
+/// maps gamepad buttons to CHIP-8 key values
+pub type ButtonMap = BTreeMap<Button, u8>;
+
+pub fn default_button_map() -> ButtonMap {
+    BTreeMap::from([
+        // most games use 2/4/6/8 for movement and 5 for action
+        (Button::DPadUp, 0x2),
+        (Button::DPadLeft, 0x4),
+        (Button::DPadRight, 0x6),
+        (Button::DPadDown, 0x8),
+        (Button::South, 0x5),
+        (Button::East, 0xC),
+        (Button::North, 0xD),
+        (Button::West, 0xA),
+        (Button::Select, 0x0),
+        (Button::Start, 0xF),
+    ])
+}
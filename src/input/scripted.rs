@@ -0,0 +1,35 @@
+use std::collections::VecDeque;
+
+use crate::ui::util::key_capture::KeyBindings;
+
+use super::InputSource;
+
+/// replays a fixed sequence of keypad states, one step per poll, e.g. to drive ROMs from tests
+pub struct ScriptedInput {
+    // (number of polls, keys held during those polls)
+    steps: VecDeque<(usize, Vec<u8>)>,
+}
+
+impl ScriptedInput {
+    pub fn new(steps: impl IntoIterator<Item=(usize, Vec<u8>)>) -> Self {
+        Self { steps: steps.into_iter().collect() }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn poll(&mut self, _bindings: &KeyBindings, keys: &mut [bool; 16]) {
+        while let Some((0, _)) = self.steps.front() {
+            self.steps.pop_front();
+        }
+        if let Some((remaining, held)) = self.steps.front_mut() {
+            for key in held.iter() {
+                keys[(*key & 0xF) as usize] = true;
+            }
+            *remaining -= 1;
+        }
+    }
+}
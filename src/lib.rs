@@ -1,10 +1,27 @@
-pub use emulator::{Emulator, EmulatorConfig};
+pub use emulator::{Emulator, EmulatorConfig, EmulatorEvent, RunOutcome};
 pub use errors::{Error, Result};
 pub use machine::Machine;
 
 pub mod machine;
+pub mod app;
 pub mod command;
 pub mod errors;
 pub mod ui;
 pub mod emulator;
-mod assembler;
\ No newline at end of file
+pub mod input;
+pub mod library;
+pub mod repl;
+pub mod settings;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod terminal;
+pub mod trace;
+pub mod clock;
+pub mod gdb;
+pub mod dap;
+pub mod dump;
+pub mod encoding;
+pub mod state;
+pub mod storage;
+pub mod assembler;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
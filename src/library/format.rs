@@ -0,0 +1,183 @@
+use std::path::{Path, PathBuf};
+
+use egui::Color32;
+use serde::Deserialize;
+
+use crate::{Error, Result};
+use crate::settings::DisplayColors;
+use crate::storage::{FileStorage, Storage};
+
+use super::{Platform, Quirks};
+
+/// file extensions recognised as ROMs, in the order they are tried when looking up a ROM by name
+pub const ROM_EXTENSIONS: [&str; 7] = ["rom", "ch8", "c8", "sc8", "xo8", "gif", "8o"];
+
+/// directory searched for ROMs given by name only (e.g. `:load ibm`)
+pub const ROM_DIRECTORY: &str = "roms";
+
+pub enum RomFormat {
+    // plain program bytes for the given platform
+    Binary(Platform),
+    // Octo cartridge: a GIF with the program source and options hidden in the image data
+    OctoCartridge,
+    // Octo assembly source, compiled on load
+    OctoSource,
+}
+
+impl RomFormat {
+    /// guesses the format from the file extension, assuming unknown extensions are CHIP-8 binaries
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        let extension = path.as_ref().extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+        match extension.as_deref() {
+            Some("sc8") => Self::Binary(Platform::SuperChip),
+            Some("xo8") => Self::Binary(Platform::XoChip),
+            Some("gif") => Self::OctoCartridge,
+            Some("8o") => Self::OctoSource,
+            _ => Self::Binary(Platform::Chip8),
+        }
+    }
+}
+
+/// finds a ROM file by path, or by name (without extension) in the working directory or `roms/`
+pub fn find_rom(name_or_path: impl AsRef<Path>) -> Option<PathBuf> {
+    find_rom_in(&FileStorage, name_or_path.as_ref())
+}
+
+/// finds a ROM like `find_rom`, in `storage`
+pub fn find_rom_in(storage: &dyn Storage, name_or_path: &Path) -> Option<PathBuf> {
+    if storage.exists(name_or_path) {
+        return Some(name_or_path.to_path_buf());
+    }
+    [PathBuf::new(), PathBuf::from(ROM_DIRECTORY)].into_iter()
+        .flat_map(|directory| ROM_EXTENSIONS.map(|extension| directory.join(name_or_path).with_extension(extension)))
+        .find(|path| storage.exists(path))
+}
+
+pub fn is_rom_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| ROM_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// settings a ROM file (or the ROM database) says the ROM should be run with
+#[derive(Clone, Debug, PartialEq)]
+pub struct RomOptions {
+    pub platform: Platform,
+    pub quirks: Quirks,
+    // instructions per 60Hz frame
+    pub tickrate: Option<u64>,
+    pub colors: Option<DisplayColors>,
+}
+
+impl RomOptions {
+    pub fn new(platform: Platform) -> Self {
+        Self {
+            platform,
+            quirks: Quirks::default(),
+            tickrate: None,
+            colors: None,
+        }
+    }
+}
+
+/// a decoded Octo cartridge
+pub struct OctoCartridge {
+    // Octo assembly source
+    pub program: String,
+    pub options: OctoOptions,
+}
+
+/// the subset of Octo's options this interpreter understands
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct OctoOptions {
+    pub tickrate: Option<u64>,
+    pub fill_color: Option<String>,
+    pub background_color: Option<String>,
+    pub shift_quirks: Option<bool>,
+    pub load_store_quirks: Option<bool>,
+    pub jump_quirks: Option<bool>,
+    // memory size Octo allows the program to use, which tells the platforms apart
+    pub max_size: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct CartridgePayload {
+    program: String,
+    #[serde(default)]
+    options: OctoOptions,
+}
+
+impl OctoCartridge {
+    /// Octo hides its payload in the low two bits of every pixel's palette index, over all frames of
+    /// the GIF: each four pixels make up a byte (most significant bits first), and the bytes are a
+    /// 32-bit big-endian length followed by that many bytes of JSON holding the program and options
+    pub fn decode(gif: &[u8]) -> Result<Self> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(gif).map_err(cartridge_error)?;
+        let mut bits = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().map_err(cartridge_error)? {
+            bits.extend(frame.buffer.iter().map(|index| index & 0b11));
+        }
+        let bytes: Vec<u8> = bits.chunks_exact(4)
+            .map(|chunk| chunk.iter().fold(0, |byte, bits| (byte << 2) | bits))
+            .collect();
+        if bytes.len() < 4 {
+            return Err(Error::RomFormatError(String::from("cartridge contains no data")));
+        }
+        let length = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+        let json = bytes.get(4..4 + length)
+            .ok_or_else(|| Error::RomFormatError(String::from("cartridge data is truncated")))?;
+        let payload: CartridgePayload = serde_json::from_slice(json)?;
+        Ok(Self {
+            program: payload.program,
+            options: payload.options,
+        })
+    }
+
+    pub fn rom_options(&self) -> RomOptions {
+        let options = &self.options;
+        let platform = match options.max_size {
+            Some(size) if size > 3584 => Platform::XoChip,
+            Some(size) if size > 3216 => Platform::SuperChip,
+            _ => Platform::Chip8,
+        };
+        let colors = match (parse_color(&options.fill_color), parse_color(&options.background_color)) {
+            (None, None) => None,
+            (foreground, background) => {
+                let default = DisplayColors::new();
+                Some(DisplayColors {
+                    foreground: foreground.unwrap_or(default.foreground),
+                    background: background.unwrap_or(default.background),
+                })
+            }
+        };
+        RomOptions {
+            platform,
+            quirks: Quirks {
+                bitshift_ignore_y: options.shift_quirks,
+                // Octo's quirk is *not* incrementing the index register
+                load_increment_index: options.load_store_quirks.map(|quirk| !quirk),
+                jump_xnn: options.jump_quirks,
+            },
+            tickrate: options.tickrate,
+            colors,
+        }
+    }
+}
+
+fn cartridge_error(error: gif::DecodingError) -> Error {
+    Error::RomFormatError(format!("invalid cartridge image: {}", error))
+}
+
+// parses an Octo colour of the form `#RRGGBB`
+fn parse_color(color: &Option<String>) -> Option<Color32> {
+    let hex = color.as_ref()?.strip_prefix('#')?;
+    if hex.len() != 6 { return None; }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let [_, r, g, b] = value.to_be_bytes();
+    Some(Color32::from_rgb(r, g, b))
+}
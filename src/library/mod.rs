@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+pub use format::{find_rom, find_rom_in, is_rom_file, OctoCartridge, OctoOptions, ROM_DIRECTORY, ROM_EXTENSIONS, RomFormat, RomOptions};
+
+use crate::machine::MachineConfig;
+use crate::Result;
+
+mod format;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub enum Platform {
+    #[serde(rename = "chip-8")]
+    Chip8,
+    #[serde(rename = "schip")]
+    SuperChip,
+    #[serde(rename = "xo-chip")]
+    XoChip,
+}
+
+impl Platform {
+    /// whether this interpreter can run ROMs for the platform
+    pub fn is_supported(&self) -> bool {
+        matches!(self, Self::Chip8)
+    }
+}
+
+impl Display for Platform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::Chip8 => "CHIP-8",
+            Self::SuperChip => "SUPER-CHIP",
+            Self::XoChip => "XO-CHIP",
+        })
+    }
+}
+
+/// interpreter behaviour a ROM depends on (see `MachineConfig`), where unset means no preference
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(default)]
+pub struct Quirks {
+    pub bitshift_ignore_y: Option<bool>,
+    pub jump_xnn: Option<bool>,
+    pub load_increment_index: Option<bool>,
+}
+
+impl Quirks {
+    pub fn apply(&self, config: &mut MachineConfig) {
+        if let Some(value) = self.bitshift_ignore_y {
+            config.bitshift_ignore_y = value;
+        }
+        if let Some(value) = self.jump_xnn {
+            config.jump_xnn = value;
+        }
+        if let Some(value) = self.load_increment_index {
+            config.load_increment_index = value;
+        }
+    }
+
+    /// combines with other quirks, which take precedence where set
+    pub fn merge(&mut self, other: &Quirks) {
+        self.bitshift_ignore_y = other.bitshift_ignore_y.or(self.bitshift_ignore_y);
+        self.jump_xnn = other.jump_xnn.or(self.jump_xnn);
+        self.load_increment_index = other.load_increment_index.or(self.load_increment_index);
+    }
+}
+
+/// what the metadata database knows about a ROM
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct RomInfo {
+    pub title: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    pub platform: Platform,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub quirks: Quirks,
+    // action (e.g. "left", "fire") -> keypad keys used for it
+    #[serde(default)]
+    pub keys: BTreeMap<String, Vec<u8>>,
+}
+
+/// ROM metadata keyed by SHA-1 of the ROM contents
+pub struct RomDatabase {
+    roms: BTreeMap<String, RomInfo>,
+}
+
+impl RomDatabase {
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(Self { roms: serde_json::from_str(json)? })
+    }
+
+    /// the database bundled with the application
+    pub fn bundled() -> &'static Self {
+        static DATABASE: OnceLock<RomDatabase> = OnceLock::new();
+        DATABASE.get_or_init(|| {
+            Self::from_json(include_str!("rom_db.json")).expect("bundled ROM database is valid")
+        })
+    }
+
+    pub fn lookup(&self, bytes: &[u8]) -> Option<&RomInfo> {
+        self.roms.get(&sha1(bytes))
+    }
+}
+
+pub fn sha1(bytes: &[u8]) -> String {
+    sha1_smol::Sha1::from(bytes).digest().to_string()
+}
+
+pub struct LibraryEntry {
+    pub path: PathBuf,
+    pub sha1: String,
+    pub info: Option<RomInfo>,
+}
+
+impl LibraryEntry {
+    pub fn title(&self) -> String {
+        match &self.info {
+            Some(info) => info.title.clone(),
+            None => self.path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+        }
+    }
+}
+
+/// ROMs found in the configured library directories
+pub struct RomLibrary {
+    pub entries: Vec<LibraryEntry>,
+    // files and directories the last scan couldn't read, and why
+    pub warnings: Vec<String>,
+}
+
+impl RomLibrary {
+    pub fn new() -> Self {
+        Self { entries: Vec::new(), warnings: Vec::new() }
+    }
+
+    /// rescans the given directories (recursively), identifying each ROM in the bundled database;
+    /// directories that don't exist are skipped, and anything unreadable is skipped with a warning
+    pub fn scan(&mut self, directories: &[impl AsRef<Path>]) {
+        self.entries.clear();
+        self.warnings.clear();
+        for directory in directories {
+            if directory.as_ref().is_dir() {
+                self.scan_directory(directory.as_ref());
+            }
+        }
+        self.entries.sort_by_key(|entry| entry.title().to_lowercase());
+    }
+
+    fn scan_directory(&mut self, directory: &Path) {
+        let entries = match fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(error) => return self.warn(directory, error),
+        };
+        for entry in entries {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(error) => {
+                    self.warn(directory, error);
+                    continue;
+                }
+            };
+            if path.is_dir() {
+                self.scan_directory(&path);
+            } else if is_rom_file(&path) {
+                match fs::read(&path) {
+                    Ok(bytes) => self.entries.push(LibraryEntry {
+                        sha1: sha1(&bytes),
+                        info: RomDatabase::bundled().lookup(&bytes).cloned(),
+                        path,
+                    }),
+                    Err(error) => self.warn(&path, error),
+                }
+            }
+        }
+    }
+
+    fn warn(&mut self, path: &Path, error: io::Error) {
+        self.warnings.push(format!("skipped '{}': {}", path.display(), error));
+    }
+}
+
+impl Default for RomLibrary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
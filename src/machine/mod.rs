@@ -1,12 +1,3 @@
-pub use draw_options::DrawOptions;
-pub use instruction::{Instruction, OpCode};
-pub use machine::Machine;
-pub use types::{Address, Timer};
-
-pub mod config;
-mod draw_options;
-mod stack;
-mod machine;
-pub mod instruction;
-pub(crate) mod types;
-
+// the interpreter core lives in its own no_std crate
+pub use chipper8_core::{config, instruction, types};
+pub use chipper8_core::{Address, Coverage, DrawOptions, Frame, Instruction, Machine, MachineConfig, OpCode, Profiler, Stack, SubroutineProfile, Timer};
@@ -1,85 +1,277 @@
+use std::ops::Range;
 use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
 
 use clap::Parser;
 use eframe::NativeOptions;
-use egui::{Context, Vec2};
+use egui::Vec2;
 
+use chipper8::app::EmulatorApp;
+use chipper8::dump::{ScreenDump, ScreenFormat, StateSelection};
 use chipper8::emulator::{Emulator, EmulatorConfig};
-use chipper8::Result;
-use chipper8::ui::KeyCapture;
-use chipper8::ui::windows::Display;
+use chipper8::{Error, library, machine, Result};
+use chipper8::gdb::GdbServer;
+use chipper8::settings::{DisplayOptions, Palette, Settings};
+use chipper8::terminal;
+use chipper8::trace::{InstructionKind, TraceFilter, Tracer};
+use chipper8::ui::Rom;
+use chipper8::ui::util::BlendMode;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     // todo: split up GUI and emulator args
+    /// ROM to run: a path, or a name to look up in `roms/` (defaults to the last ROM run)
     #[arg(index = 1)]
-    rom: PathBuf,
+    rom: Option<PathBuf>,
 
-    #[arg(short, long, default_value_t = 60)]
-    fps: u64,
+    /// instructions per second (defaults to the saved setting)
+    #[arg(short, long, value_parser = clap::value_parser!(u64).range(1..))]
+    fps: Option<u64>,
 
+    /// speed multiplier, e.g. 0.5 for half speed
+    #[arg(long, default_value_t = 1.0, value_parser = parse_positive)]
+    speed: f64,
+
+    /// run as fast as possible instead of in real time
+    #[arg(long, default_value_t = false)]
+    turbo: bool,
+
+    /// step over invalid opcodes instead of stopping (defaults to the saved setting; pass `false`
+    /// to turn a saved `true` off)
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    skip_unknown_opcode: Option<bool>,
+
+    /// run without a display until the machine stops; the exit code is 0 if the program exited
+    /// (or reached --until-pc), 1 on a fault, 2 when a limit was hit and 3 when waiting for a key
     #[arg(long, default_value_t = false)]
     headless: bool,
 
+    /// stop after executing this many instructions
+    #[arg(long)]
+    max_ticks: Option<u64>,
+
+    /// stop after running for this many seconds
+    #[arg(long, value_parser = parse_positive)]
+    max_seconds: Option<f64>,
+
+    /// stop when the program counter reaches this address (hex, e.g. 0x2A0)
+    #[arg(long, value_parser = parse_address)]
+    until_pc: Option<u16>,
+
+    /// stop when the program jumps to itself, the usual way for CHIP-8 programs to end
+    #[arg(long, default_value_t = false)]
+    auto_exit: bool,
+
+    /// render the display in the terminal instead of opening a window
+    #[arg(long, default_value_t = false, conflicts_with = "headless")]
+    tui: bool,
+
+    /// write the final machine state to a file: JSON, or the compact binary format for `.c8s` files
     #[arg(long)]
     dump: Option<PathBuf>,
+
+    /// only dump the registers, index, program counter, stack and timers
+    #[arg(long, default_value_t = false, requires = "dump")]
+    dump_registers: bool,
+
+    /// only dump memory in an address range, e.g. `0x200..0x300` (may be repeated)
+    #[arg(long, value_parser = TraceFilter::parse_range, requires = "dump")]
+    dump_memory: Vec<Range<u16>>,
+
+    /// write the final display to a file, in the format its extension suggests (.txt for ASCII
+    /// art, .pbm, .png or .hex)
+    #[arg(long)]
+    screen: Option<PathBuf>,
+
+    /// screen dump format, overriding the file extension
+    #[arg(long, value_enum, requires = "screen")]
+    screen_format: Option<ScreenFormat>,
+
+    /// also write the display every this many frames, to files numbered by frame
+    #[arg(long, requires = "screen", value_parser = clap::value_parser!(u64).range(1..))]
+    screen_every: Option<u64>,
+
+    /// profile the run and write the ROM's coverage (executed instructions, bytes read and written,
+    /// subroutines) to a JSON file when it ends
+    #[arg(long)]
+    coverage: Option<PathBuf>,
+
+    /// display palette (defaults to the saved setting for the ROM)
+    #[arg(long, value_enum)]
+    palette: Option<Palette>,
+
+    /// size of each CHIP-8 pixel in screen pixels (defaults to the saved setting for the ROM)
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..=DisplayOptions::MAX_ZOOM as i64))]
+    zoom: Option<u16>,
+
+    /// anti-flicker frame blending (defaults to the saved setting for the ROM)
+    #[arg(long, value_enum)]
+    blend: Option<BlendMode>,
+
+    /// log executed instructions to a file, or to stdout if no file (or `-`) is given
+    #[arg(long, num_args = 0..=1, default_missing_value = "-")]
+    trace: Option<PathBuf>,
+
+    /// only trace instructions in an address range, e.g. `0x200..0x300` (may be repeated)
+    #[arg(long, value_parser = TraceFilter::parse_range)]
+    trace_range: Vec<Range<u16>>,
+
+    /// only trace instructions of a kind (may be repeated)
+    #[arg(long, value_enum)]
+    trace_kind: Vec<InstructionKind>,
+
+    /// wait for a GDB remote protocol debugger on this port (localhost only) before running
+    #[arg(long)]
+    gdb: Option<u16>,
 }
 
-impl From<&Args> for EmulatorConfig {
-    fn from(args: &Args) -> Self {
-        Self {
-            rom_path: args.rom.clone(),
-            fps: args.fps,
-            dump_path: args.dump.clone(),
-        }
+fn parse_positive(text: &str) -> std::result::Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(speed) if speed > 0.0 && speed.is_finite() => Ok(speed),
+        _ => Err(format!("expected a positive number, found `{}`", text)),
     }
 }
 
+fn parse_address(text: &str) -> std::result::Result<u16, String> {
+    let hex = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).unwrap_or(text);
+    match u16::from_str_radix(hex, 16) {
+        Ok(address) if address < 0x1000 => Ok(address),
+        _ => Err(format!("expected a 12 bit hex address, found `{}`", text)),
+    }
+}
+
+fn tracer(args: &Args) -> Result<Option<Tracer>> {
+    let Some(path) = &args.trace else { return Ok(None); };
+    let filter = TraceFilter {
+        ranges: args.trace_range.clone(),
+        kinds: args.trace_kind.iter().copied().collect(),
+    };
+    let tracer = Tracer::new().with_filter(filter);
+    if path.as_os_str() != "-" {
+        return Ok(Some(tracer.with_file(path)?));
+    }
+    if args.tui {
+        return Err(Error::MissingArgument(String::from("--trace needs a file with --tui, which draws to stdout")));
+    }
+    Ok(Some(tracer.with_stdout()))
+}
+
+fn debugger(args: &Args) -> Result<Option<GdbServer>> {
+    let Some(port) = args.gdb else { return Ok(None); };
+    let server = GdbServer::bind(port)?;
+    eprintln!("Waiting for a debugger on {}", server.local_addr()?);
+    Ok(Some(server))
+}
+
+fn screen_dump(args: &Args) -> Option<ScreenDump> {
+    let mut screen_dump = ScreenDump::new(args.screen.as_ref()?);
+    if let Some(format) = args.screen_format {
+        screen_dump.format = format;
+    }
+    screen_dump.every = args.screen_every;
+    Some(screen_dump)
+}
+
+// display options saved for the ROM, with command line overrides
+fn display_options(args: &Args, settings: &Settings, emulator: &Emulator) -> DisplayOptions {
+    let mut options = match (settings.rom_display.get(&emulator.rom_name), emulator.rom_options.colors) {
+        // colours embedded in the ROM apply unless the ROM has saved display options
+        (None, Some(colors)) => DisplayOptions {
+            palette: Palette::Custom,
+            custom_colors: colors,
+            ..settings.display
+        },
+        _ => settings.display_for(&emulator.rom_name),
+    };
+    if let Some(palette) = args.palette {
+        options.palette = palette;
+    }
+    if let Some(zoom) = args.zoom {
+        options.zoom = zoom as usize;
+    }
+    if let Some(blend) = args.blend {
+        options.blend_mode = blend;
+    }
+    options
+}
+
+// command line arguments override saved settings
+fn rom_path(args: &Args, settings: &Settings) -> Result<PathBuf> {
+    let rom = match (&args.rom, &settings.last_rom) {
+        (Some(rom), _) => rom.clone(),
+        (None, Some(rom)) => PathBuf::from(rom),
+        (None, None) => Err(Error::MissingArgument(String::from("no ROM given and no last ROM saved")))?,
+    };
+    library::find_rom(&rom).ok_or_else(|| Error::RomNotFound(rom.display().to_string()))
+}
+
+fn emulator_config(args: &Args, settings: &Settings) -> Result<EmulatorConfig> {
+    Ok(EmulatorConfig {
+        fps: args.fps.unwrap_or(settings.frames_per_second.max(1)),
+        speed: args.speed,
+        turbo: args.turbo,
+        dump_path: args.dump.clone(),
+        dump_selection: StateSelection { registers: args.dump_registers, memory: args.dump_memory.clone() },
+        screen_dump: screen_dump(args),
+        coverage_path: args.coverage.clone(),
+        trace: tracer(args)?,
+        skip_unknown_opcode: args.skip_unknown_opcode.unwrap_or(settings.skip_unknown_opcode),
+        gdb: debugger(args)?,
+        max_ticks: args.max_ticks,
+        max_duration: args.max_seconds.map(Duration::from_secs_f64),
+    })
+}
+
 fn main() -> Result<()> {
-    let mut args = Args::parse();
-    args.rom.set_extension("rom");
-    let mut emulator = Emulator::new(EmulatorConfig::from(&args))?;
-    if !args.headless {
+    let args = Args::parse();
+    let mut settings = Settings::load_or_default();
+    let rom_path = rom_path(&args, &settings)?;
+    // only interactive runs are remembered, so headless (e.g. CI) runs leave the settings alone
+    if args.rom.is_some() && !args.headless {
+        settings.last_rom = Some(rom_path.to_string_lossy().into_owned());
+        if let Err(error) = settings.save() {
+            eprintln!("Warning: could not save settings: {}", error);
+        }
+    }
+    let rom = Rom::from_file(&rom_path)?;
+    for warning in &rom.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    let mut emulator = Emulator::new(rom, emulator_config(&args, &settings)?);
+    println!("CHIPPER-8: running ROM '{}'.", emulator.rom_name);
+    emulator.on_event(|event| eprintln!("{}", event));
+    if args.auto_exit {
+        emulator.machine.config.auto_exit = true;
+    }
+    emulator.breakpoints.extend(args.until_pc);
+    let rom_options = &emulator.rom_options;
+    if !rom_options.platform.is_supported() {
+        eprintln!("Warning: {} ROMs are not supported", rom_options.platform);
+    }
+    // the ROM's own speed beats the saved setting, but not the command line
+    if let (None, Some(tickrate)) = (args.fps, rom_options.tickrate) {
+        emulator.config.fps = tickrate * 60;
+    }
+    if args.tui {
+        terminal::run_emulator(&mut emulator)?;
+    } else if !args.headless {
         let mut native_options = NativeOptions::default();
         native_options.resizable = true;
         native_options.run_and_return = false;
-        native_options.initial_window_size = Some(Vec2 { x: 280.0, y: 140.0 });
+        let display_options = display_options(&args, &settings, &emulator);
+        let zoom = display_options.zoom as f32;
+        native_options.initial_window_size = Some(Vec2 {
+            x: zoom * machine::config::DISPLAY_WIDTH as f32 + 24.0,
+            y: zoom * machine::config::DISPLAY_HEIGHT as f32 + 12.0,
+        });
         eframe::run_native("CHIPPER-8", native_options,
-                           Box::new(|cc| Box::new(EmulatorApp::new(cc, emulator))));
+                           Box::new(move |cc| Box::new(EmulatorApp::new(cc, emulator, &display_options))));
     } else {
         // no display: useful for testing when combined with state dump
-        emulator.run()?;
+        let outcome = emulator.run()?;
+        process::exit(outcome.exit_code());
     }
     Ok(())
 }
-
-struct EmulatorApp {
-    emulator: Emulator,
-    display: Display,
-    key_capture: KeyCapture,
-}
-
-impl EmulatorApp {
-    fn new(_cc: &eframe::CreationContext<'_>, emulator: Emulator) -> Self {
-        Self {
-            emulator,
-            display: Display::minimal(),
-            key_capture: KeyCapture::new(),
-        }
-    }
-}
-
-impl eframe::App for EmulatorApp {
-    fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(
-            ctx, |ui| {
-                self.display.ui_stateless(ui, &mut self.emulator.machine);
-                self.key_capture.update(ui);
-            },
-        );
-        self.emulator.machine.key_buffer = self.key_capture.key();
-        self.emulator.tick();
-        ctx.request_repaint_after(self.emulator.config.frame_time());
-    }
-}
\ No newline at end of file
@@ -0,0 +1,351 @@
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::{Error, Result};
+use crate::assembler::Symbols;
+use crate::clock::{self, FrameClock, FrameRunner};
+use crate::command::{Command, MachineState, MetaCommand, Profiling, Tracing};
+use crate::gdb::GdbServer;
+use crate::library;
+use crate::machine::{Coverage, Machine, MachineConfig};
+use crate::machine::instruction::{Flow, Instruction};
+use crate::settings::{Settings, UserTag};
+use crate::state::{self, RomIdentity};
+use crate::storage::{self, Storage};
+use crate::trace::Tracer;
+use crate::ui::{MemoryTag, Rom, State, StopCondition};
+use crate::ui::util::Address;
+
+// command execution shared by the REPL front-ends (egui window and terminal)
+pub struct Repl {
+    pub machine: Machine,
+    pub state: State,
+    // where ROMs are loaded from and states and coverage are written to
+    pub storage: Box<dyn Storage>,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self {
+            machine: Machine::new(),
+            state: State::new(),
+            storage: storage::platform_storage(),
+        }
+    }
+
+    /// applies saved settings, reloading the ROM that was loaded when they were saved
+    pub fn restore(&mut self, settings: &Settings) {
+        self.state.apply_settings(settings);
+        if let Some(rom) = settings.last_rom.clone() {
+            self.state.command_buffer = Some(Command::Meta(MetaCommand::LoadRom(rom, None)));
+            self.execute_buffered();
+        }
+    }
+
+    pub fn execute(&mut self, command: &Command) -> Result<()> {
+        match command {
+            Command::Instruction(instruction) => {
+                // user entered a machine instruction at the prompt
+                // so we should suspend the VM main loop if running
+                self.state.running = false;
+                // todo: Machine::execute should also return result
+                self.machine.execute(instruction)?;
+                Ok(())
+            }
+            Command::Meta(meta) => self.execute_meta(meta),
+        }
+    }
+
+    fn execute_meta(&mut self, command: &MetaCommand) -> Result<()> {
+        match command {
+            MetaCommand::Reset(state) => {
+                self.state.running = false;
+                self.machine.reset();
+                if let Some(state) = state {
+                    match state {
+                        MachineState::Demo => self.machine.demo()?,
+                    };
+                };
+            }
+            MetaCommand::LoadRom(name_or_path, address) => {
+                let path = library::find_rom_in(self.storage.as_ref(), Path::new(name_or_path))
+                    .ok_or_else(|| Error::RomNotFound(name_or_path.clone()))?;
+                let mut rom = Rom::from_storage(self.storage.as_ref(), &path)?;
+                self.state.running = false;
+                if let Some(mut rom) = self.state.unload_rom() {
+                    rom.unload(&mut self.machine);
+                }
+                // quirks required by the previous ROM shouldn't carry over to this one
+                self.machine.config = MachineConfig {
+                    auto_exit: self.machine.config.auto_exit,
+                    ..MachineConfig::new()
+                };
+                rom.load(&mut self.machine, address.as_ref());
+                if let Some(warning) = rom.warnings.first() {
+                    self.state.error = Some(Error::Warning(warning.clone()));
+                }
+                self.state.load_rom(rom);
+                self.state.last_rom = Some(path.to_string_lossy().into_owned());
+            }
+            MetaCommand::UnloadRom => {
+                self.state.running = false;
+                if let Some(mut rom) = self.state.unload_rom() {
+                    rom.unload(&mut self.machine);
+                }
+                self.state.last_rom = None;
+            }
+            MetaCommand::DumpMachine(path) => {
+                let rom = self.state.rom.as_ref().map(RomIdentity::of);
+                state::save_to(self.storage.as_mut(), Path::new(path), &self.machine, rom.as_ref())?;
+            }
+            MetaCommand::LoadMachine(name_or_path) => {
+                let saved = state::load_from(self.storage.as_ref(), Path::new(name_or_path))?;
+                // carry on, but say so: the state's memory still holds the ROM it was saved with
+                if saved.rom_differs(self.state.rom.as_ref()) {
+                    let name = saved.rom.as_ref().map_or("", |rom| rom.name.as_str());
+                    self.state.error = Some(Error::StateRomMismatch(String::from(name)));
+                }
+                self.machine = saved.machine;
+            }
+            MetaCommand::Tick => {
+                self.state.running = false;
+                self.tick()?;
+            }
+            MetaCommand::Play => {
+                self.state.running = true;
+            }
+            MetaCommand::Pause => {
+                self.state.running = false;
+            }
+            MetaCommand::PlayPause => {
+                self.state.running = !self.state.running;
+            }
+            MetaCommand::Finish => {
+                let depth = self.machine.stack.pointer.checked_sub(1).ok_or(Error::NotInSubroutine)?;
+                self.state.stop_condition = Some(StopCondition::StackDepth(depth));
+                self.state.running = true;
+            }
+            MetaCommand::Next => {
+                // a call runs until it returns, anything else is a single step
+                if let Instruction::Flow(Flow::Call { args: _ }) = self.machine.next_instruction()? {
+                    let address = u16::from(&self.machine.program_counter) + 2;
+                    let depth = self.machine.stack.pointer;
+                    self.state.stop_condition = Some(StopCondition::Return { address, depth });
+                    self.state.running = true;
+                } else {
+                    self.state.running = false;
+                    self.tick()?;
+                }
+            }
+            MetaCommand::Until(location) => {
+                self.state.stop_condition = Some(StopCondition::Address(self.state.resolve(location)?));
+                self.state.running = true;
+            }
+            MetaCommand::Run(ticks) => {
+                self.state.stop_condition = Some(StopCondition::Ticks(*ticks));
+                self.state.running = *ticks > 0;
+            }
+            MetaCommand::LoadSymbols(path) => {
+                let symbols = Symbols::load(path)?;
+                let rom = self.state.rom.as_mut()
+                    .ok_or_else(|| Error::MissingArgument(String::from("a ROM must be loaded before its symbols")))?;
+                rom.symbols = Some(symbols);
+                self.state.update_rom_tags();
+            }
+            MetaCommand::Break(location) => {
+                let address = self.state.resolve(location)?;
+                self.state.breakpoints.insert(address);
+            }
+            MetaCommand::Unbreak(location) => {
+                let address = self.state.resolve(location)?;
+                self.state.breakpoints.remove(&address);
+            }
+            MetaCommand::Tag(start, end, name) => {
+                let (start, end) = (start.as_index() as u16, end.as_index() as u16);
+                if end <= start {
+                    return Err(Error::MetaSyntaxError(String::from("tag end address must be after its start")));
+                }
+                self.state.add_user_tag(UserTag { name: name.clone(), start, end })?;
+            }
+            MetaCommand::Untag(name) => self.state.remove_user_tag(name)?,
+            MetaCommand::Profile(Profiling::On) => self.machine.set_profiling(true),
+            MetaCommand::Profile(Profiling::Off) => self.machine.set_profiling(false),
+            MetaCommand::Profile(Profiling::Reset) => {
+                if self.machine.profiler.is_some() {
+                    self.machine.set_profiling(true);
+                }
+            }
+            MetaCommand::Coverage(path) => {
+                let coverage = serde_json::to_string_pretty(&self.coverage()?)?;
+                self.storage.write(Path::new(path), coverage.as_bytes())?;
+            }
+            MetaCommand::Trace(Tracing::On) => {
+                self.state.tracer.get_or_insert_with(|| Tracer::new().with_buffer());
+            }
+            MetaCommand::Trace(Tracing::Off) => {
+                if let Some(mut tracer) = self.state.tracer.take() {
+                    tracer.flush()?;
+                }
+            }
+            MetaCommand::Trace(Tracing::Clear) => {
+                if let Some(tracer) = &mut self.state.tracer {
+                    tracer.clear();
+                }
+            }
+            MetaCommand::Trace(Tracing::File(path)) => {
+                let tracer = self.state.tracer.take().unwrap_or_else(|| Tracer::new().with_buffer());
+                self.state.tracer = Some(tracer.with_file(path)?);
+            }
+            MetaCommand::Gdb(Some(port)) => {
+                // the debugger decides when the machine runs
+                self.state.running = false;
+                self.state.gdb = Some(GdbServer::bind(*port)?);
+            }
+            MetaCommand::Gdb(None) => self.state.gdb = None,
+        };
+        Ok(())
+    }
+
+    /// coverage of the loaded ROM by the profile collected so far
+    pub fn coverage(&self) -> Result<Coverage> {
+        let profiler = self.machine.profiler.as_ref()
+            .ok_or_else(|| Error::MissingArgument(String::from("profiling must be enabled with `:profile on`")))?;
+        let range = self.state.rom.as_ref().and_then(|rom| rom.loaded_range())
+            .ok_or_else(|| Error::MissingArgument(String::from("a ROM must be loaded to report its coverage")))?;
+        Ok(profiler.coverage(range))
+    }
+
+    pub fn tick(&mut self) -> Result<()> {
+        let instruction = self.machine.next_instruction()?;
+        self.state.command_history.append(&Command::Instruction(instruction), false);
+        match &mut self.state.tracer {
+            Some(tracer) => tracer.tick(&mut self.machine)?,
+            None => self.machine.tick()?,
+        }
+        Ok(())
+    }
+
+    /// executes the command waiting in the state's command buffer (if any), recording it in the
+    /// command history and any resulting error in the state
+    pub fn execute_buffered(&mut self) {
+        if let Some(command) = &self.state.command_buffer.take() {
+            self.state.command_history.append(command, true);
+            match self.execute(command) {
+                Ok(_) => {}
+                Err(error) => {
+                    self.state.error = Some(error);
+                    self.state.running = false;
+                }
+            };
+        };
+    }
+
+    /// one step of the VM main loop: callers are responsible for timing
+    pub fn step_running(&mut self) {
+        if !self.state.running {
+            // pausing (for whatever reason) cancels `:finish` and friends
+            self.state.stop_condition = None;
+            return;
+        }
+        self.state.error = self.tick().err();
+        if let Some(error) = &self.state.error {
+            self.state.running = false;
+            if let Error::InvalidOpCode(_) = error {
+                if self.state.skip_unknown_opcode {
+                    self.machine.program_counter.step();
+                    self.state.running = true;
+                }
+            }
+            return;
+        }
+        let address = self.machine.program_counter.as_index() as u16;
+        if self.state.breakpoints.contains(&address) {
+            self.state.running = false;
+            let description = self.state.describe(address)
+                .map_or(String::new(), |description| format!(" {}", description));
+            self.state.error = Some(Error::Breakpoint(format!("{}{}", Address::from(address), description)));
+        }
+        let program_counter = u16::from(&self.machine.program_counter);
+        let depth = self.machine.stack.pointer;
+        let stop = match &mut self.state.stop_condition {
+            Some(StopCondition::StackDepth(max_depth)) => depth <= *max_depth,
+            Some(StopCondition::Address(address)) => program_counter == *address,
+            Some(StopCondition::Return { address, depth: max_depth }) => program_counter == *address && depth <= *max_depth,
+            Some(StopCondition::Ticks(ticks)) => match ticks.checked_sub(1) {
+                Some(remaining) => {
+                    *ticks = remaining;
+                    remaining == 0
+                }
+                None => true,
+            },
+            None => false,
+        };
+        if stop {
+            self.state.running = false;
+            self.state.stop_condition = None;
+        }
+    }
+
+    /// services the debugger started with `:gdb` (if any), executing at most one instruction for it:
+    /// like `step_running`, callers are responsible for timing
+    pub fn poll_gdb(&mut self) {
+        let Some(mut gdb) = self.state.gdb.take() else { return; };
+        match gdb.poll(&mut self.machine, &mut self.state.breakpoints, 1) {
+            Ok(true) => self.state.gdb = Some(gdb),
+            Ok(false) => {}
+            Err(error) => self.state.error = Some(error),
+        }
+    }
+
+    /// the VM main loop for front-ends that call this whenever they redraw: executes the frames due
+    /// since the last call (or in turbo mode, as many as fit in one frame's time) at the configured
+    /// speed
+    pub fn run_frames(&mut self) {
+        if !self.state.running {
+            // a paused machine forgets why it was running, so `:play` doesn't resume an old `:until`
+            self.state.stop_condition = None;
+            self.state.clock.reset();
+            return;
+        }
+        // `step_running` records why running stopped in the state
+        let _ = clock::run_frames(self, self.state.turbo);
+    }
+
+    pub fn update_memory_tags(&mut self) {
+        self.state.memory_tags.insert(MemoryTag::ProgramCounter, self.machine.program_counter.as_range(2));
+        self.state.memory_tags.insert(MemoryTag::Index, self.machine.index.as_range(1));
+        self.state.memory_tags.retain(|tag, _| !matches!(tag, MemoryTag::Breakpoint { .. }));
+        for address in &self.state.breakpoints {
+            let start = *address as usize;
+            self.state.memory_tags.insert(MemoryTag::Breakpoint { address: *address }, start..start + 2);
+        }
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameRunner for Repl {
+    // running stopped, e.g. at a breakpoint
+    type Stop = ();
+
+    fn clock(&mut self) -> &mut FrameClock {
+        &mut self.state.clock
+    }
+
+    fn run_frame(&mut self) -> ControlFlow<()> {
+        let instructions = self.state.clock.instructions_per_frame(self.state.instructions_per_second());
+        self.state.frames += 1;
+        self.machine.tick_timers();
+        for _ in 0..instructions {
+            self.step_running();
+            if !self.state.running {
+                return ControlFlow::Break(());
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
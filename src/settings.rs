@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::ui::util::BlendMode;
+
+/// directory for all user configuration files (settings, key bindings, ...)
+pub fn config_dir() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("chipper8"))
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct DisplayColors {
+    pub foreground: Color32,
+    pub background: Color32,
+}
+
+impl DisplayColors {
+    pub fn new() -> Self {
+        Self {
+            foreground: Color32::WHITE,
+            background: Color32::BLACK,
+        }
+    }
+}
+
+impl Default for DisplayColors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq, clap::ValueEnum)]
+pub enum Palette {
+    Classic,
+    Green,
+    Amber,
+    Lcd,
+    Custom,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 5] = [Self::Classic, Self::Green, Self::Amber, Self::Lcd, Self::Custom];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Classic => "Classic",
+            Self::Green => "Green phosphor",
+            Self::Amber => "Amber phosphor",
+            Self::Lcd => "LCD",
+            Self::Custom => "Custom",
+        }
+    }
+
+    /// colours of the palette, or `None` for the custom palette
+    pub fn colors(&self) -> Option<DisplayColors> {
+        let (foreground, background) = match self {
+            Self::Classic => (Color32::WHITE, Color32::BLACK),
+            Self::Green => (Color32::from_rgb(0x33, 0xFF, 0x66), Color32::from_rgb(0x02, 0x12, 0x06)),
+            Self::Amber => (Color32::from_rgb(0xFF, 0xB0, 0x00), Color32::from_rgb(0x14, 0x0C, 0x00)),
+            Self::Lcd => (Color32::from_rgb(0x0F, 0x38, 0x0F), Color32::from_rgb(0x9B, 0xBC, 0x0F)),
+            Self::Custom => return None,
+        };
+        Some(DisplayColors { foreground, background })
+    }
+}
+
+/// how the CHIP-8 display is drawn: palette, scaling and CRT-style effects
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DisplayOptions {
+    pub palette: Palette,
+    // colours used by the custom palette
+    pub custom_colors: DisplayColors,
+    // integer zoom: size of each CHIP-8 pixel in screen pixels
+    pub zoom: usize,
+    // anti-flicker blending of consecutive frames
+    pub blend_mode: BlendMode,
+    // phosphor persistence: fraction of its brightness a pixel keeps each frame after being switched
+    // off (decay blending)
+    pub persistence: f32,
+    // number of frames combined (OR blending)
+    pub blend_frames: usize,
+    pub scanlines: bool,
+}
+
+impl DisplayOptions {
+    pub const MAX_ZOOM: usize = 16;
+
+    pub fn new() -> Self {
+        Self {
+            palette: Palette::Classic,
+            custom_colors: DisplayColors::new(),
+            zoom: 4,
+            blend_mode: BlendMode::Raw,
+            persistence: 0.5,
+            blend_frames: 2,
+            scanlines: false,
+        }
+    }
+
+    pub fn colors(&self) -> DisplayColors {
+        self.palette.colors().unwrap_or(self.custom_colors)
+    }
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// a user-defined memory tag covering addresses `start..end`
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct UserTag {
+    pub name: String,
+    pub start: u16,
+    pub end: u16,
+}
+
+/// application settings remembered between runs of the `repl` and `chipper8` binaries
+// (key bindings are remembered separately, see `KeyBindings`)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub frames_per_second: u64,
+    pub skip_unknown_opcode: bool,
+    pub display: DisplayOptions,
+    // ROM name -> display options used instead of `display` for that ROM
+    pub rom_display: BTreeMap<String, DisplayOptions>,
+    // REPL window name -> whether it is open
+    pub open_windows: BTreeMap<String, bool>,
+    pub last_rom: Option<String>,
+    // directories scanned by the ROM library
+    pub rom_directories: Vec<String>,
+    // ROM name -> memory tags added with `:tag`
+    pub rom_tags: BTreeMap<String, Vec<UserTag>>,
+    // memory tag name -> colour chosen in the legend, replacing the tag's default colour
+    pub tag_colors: BTreeMap<String, Color32>,
+}
+
+impl Settings {
+    // the most the speed slider goes up to
+    pub const MAX_FRAMES_PER_SECOND: u64 = 120;
+
+    pub fn new() -> Self {
+        Self {
+            frames_per_second: 60,
+            skip_unknown_opcode: false,
+            display: DisplayOptions::new(),
+            rom_display: BTreeMap::new(),
+            open_windows: BTreeMap::new(),
+            last_rom: None,
+            rom_directories: vec![String::from("roms")],
+            rom_tags: BTreeMap::new(),
+            tag_colors: BTreeMap::new(),
+        }
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        Some(config_dir()?.join("settings.json"))
+    }
+
+    /// display options for the given ROM, taking per-ROM overrides into account
+    pub fn display_for(&self, rom: &str) -> DisplayOptions {
+        self.rom_display.get(rom).copied().unwrap_or(self.display)
+    }
+
+    /// loads settings from the user config file, or the defaults if there is none yet
+    pub fn load() -> Result<Self> {
+        match Self::path() {
+            Some(path) if path.exists() => Ok(serde_json::from_str(&fs::read_to_string(path)?)?),
+            _ => Ok(Self::new()),
+        }
+    }
+
+    /// like `load` but reports errors and falls back to the defaults, since a broken settings file
+    /// shouldn't stop the application from starting
+    pub fn load_or_default() -> Self {
+        Self::load().unwrap_or_else(|error| {
+            eprintln!("Error loading settings, using defaults: {}", error);
+            Self::new()
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(path) = Self::path() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, serde_json::to_string_pretty(self)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
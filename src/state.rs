@@ -0,0 +1,281 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{Error, Result};
+use crate::encoding::{pack_pixels, unpack_pixels};
+use crate::library;
+use crate::machine::{config, Address, Machine, MachineConfig, Stack};
+use crate::storage::{FileStorage, Storage};
+use crate::ui::Rom;
+
+/// version of the save state format, bumped (with a migration) whenever the format changes
+pub const STATE_VERSION: u16 = 1;
+/// file extension for states in the binary format; other states are saved as JSON
+pub const STATE_EXTENSION: &str = "c8s";
+
+const MAGIC: &[u8; 4] = b"C8ST";
+// marks empty stack slots and key buffers in the binary format
+const NONE_ADDRESS: u16 = 0xFFFF;
+const NONE_KEY: u8 = 0xFF;
+
+/// the ROM a state was saved with
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct RomIdentity {
+    pub name: String,
+    pub sha1: String,
+}
+
+impl RomIdentity {
+    pub fn of(rom: &Rom) -> Self {
+        Self { name: rom.name.clone(), sha1: library::sha1(&rom.bytes) }
+    }
+}
+
+/// a loaded save state, migrated to the current version
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+pub struct SaveState {
+    pub version: u16,
+    pub rom: Option<RomIdentity>,
+    pub machine: Machine,
+}
+
+impl SaveState {
+    /// whether the state was saved with a different ROM than `rom` (states saved without a ROM
+    /// match any)
+    pub fn rom_differs(&self, rom: Option<&Rom>) -> bool {
+        match (&self.rom, rom) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(saved), Some(rom)) => saved.sha1 != library::sha1(&rom.bytes),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SaveStateRef<'a> {
+    version: u16,
+    rom: Option<&'a RomIdentity>,
+    machine: &'a Machine,
+}
+
+/// the state as JSON, with memory and display encoded as strings
+pub fn to_json(machine: &Machine, rom: Option<&RomIdentity>) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&SaveStateRef { version: STATE_VERSION, rom, machine })?)
+}
+
+/// reads a JSON state of any version, including bare machine states from before states had a header
+pub fn from_json(text: &str) -> Result<SaveState> {
+    let mut state: Value = serde_json::from_str(text)?;
+    loop {
+        let version = match state.get("version") {
+            None => 0,
+            Some(version) => version.as_u64().ok_or_else(|| invalid("version is not a number"))?,
+        };
+        state = match version {
+            // the machine state on its own
+            0 => json!({ "version": 1, "rom": null, "machine": state }),
+            version if version == STATE_VERSION as u64 => {
+                let state: SaveState = serde_json::from_value(state)?;
+                check(&state.machine)?;
+                return Ok(state);
+            }
+            version => return Err(invalid(&format!("unsupported version {}", version))),
+        };
+    }
+}
+
+/// the state in the compact binary format: a header with the version and ROM identity, then the
+/// machine with the display packed into bits
+pub fn to_bytes(machine: &Machine, rom: Option<&RomIdentity>) -> Vec<u8> {
+    let mut writer = Writer::default();
+    writer.bytes.extend(MAGIC);
+    writer.u16(STATE_VERSION);
+    match rom {
+        Some(rom) => {
+            writer.u8(1);
+            writer.sized(rom.name.as_bytes());
+            writer.sized(rom.sha1.as_bytes());
+        }
+        None => writer.u8(0),
+    }
+    writer.sized(&machine.registers);
+    writer.u8(machine.stack.pointer as u8);
+    writer.u8(machine.stack.data.len() as u8);
+    for address in &machine.stack.data {
+        writer.u16(address.as_ref().map_or(NONE_ADDRESS, u16::from));
+    }
+    writer.u16(u16::from(&machine.program_counter));
+    writer.u16(u16::from(&machine.index));
+    writer.u8(machine.delay_timer);
+    writer.u8(machine.sound_timer);
+    writer.u8(machine.key_buffer.unwrap_or(NONE_KEY));
+    let config = &machine.config;
+    let quirks = [config.bitshift_ignore_y, config.jump_xnn, config.load_increment_index, config.auto_exit];
+    writer.u8(quirks.iter().enumerate().fold(0, |flags, (i, quirk)| flags | (*quirk as u8) << i));
+    writer.sized(&machine.memory);
+    writer.u16(machine.display.len() as u16);
+    writer.bytes.extend(pack_pixels(&machine.display));
+    writer.bytes
+}
+
+pub fn from_bytes(bytes: &[u8]) -> Result<SaveState> {
+    let mut reader = Reader { bytes };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(invalid("not a save state"));
+    }
+    let version = reader.u16()?;
+    if version == 0 || version > STATE_VERSION {
+        return Err(invalid(&format!("unsupported version {}", version)));
+    }
+    let rom = match reader.u8()? {
+        0 => None,
+        _ => Some(RomIdentity { name: reader.string()?, sha1: reader.string()? }),
+    };
+    let registers = reader.sized()?.to_vec();
+    let pointer = reader.u8()? as usize;
+    let data = (0..reader.u8()?)
+        .map(|_| match reader.u16()? {
+            NONE_ADDRESS => Ok(None),
+            address => Ok(Some(Address::try_from(address)?)),
+        })
+        .collect::<Result<_>>()?;
+    let program_counter = Address::try_from(reader.u16()?)?;
+    let index = Address::try_from(reader.u16()?)?;
+    let delay_timer = reader.u8()?;
+    let sound_timer = reader.u8()?;
+    let key_buffer = Some(reader.u8()?).filter(|key| *key != NONE_KEY);
+    let flags = reader.u8()?;
+    let quirk = |i: u8| flags & 1 << i != 0;
+    let config = MachineConfig {
+        bitshift_ignore_y: quirk(0),
+        jump_xnn: quirk(1),
+        load_increment_index: quirk(2),
+        auto_exit: quirk(3),
+    };
+    let memory = reader.sized()?.to_vec();
+    let pixels = reader.u16()? as usize;
+    let display = unpack_pixels(reader.take(pixels.div_ceil(8))?, pixels);
+    let machine = Machine {
+        registers,
+        stack: Stack { data, pointer },
+        memory,
+        display,
+        program_counter,
+        index,
+        delay_timer,
+        sound_timer,
+        key_buffer,
+        config,
+        profiler: None,
+    };
+    check(&machine)?;
+    Ok(SaveState { version: STATE_VERSION, rom, machine })
+}
+
+// a state's machine must have the sizes the interpreter indexes with, as a truncated or crafted
+// state would otherwise load fine and panic later
+fn check(machine: &Machine) -> Result<()> {
+    let sizes = [
+        ("registers", machine.registers.len(), config::NUM_REGISTERS),
+        ("memory", machine.memory.len(), config::MEMORY_SIZE),
+        ("display", machine.display.len(), config::DISPLAY_SIZE),
+        ("stack", machine.stack.data.len(), config::STACK_SIZE),
+    ];
+    for (name, size, expected) in sizes {
+        if size != expected {
+            return Err(invalid(&format!("{} has size {}, expected {}", name, size, expected)));
+        }
+    }
+    let stack = &machine.stack;
+    if stack.pointer > stack.data.len() || stack.data[..stack.pointer].iter().any(Option::is_none) {
+        return Err(invalid("stack pointer doesn't match the stack"));
+    }
+    Ok(())
+}
+
+/// writes the state in the binary format if the path has the state extension, otherwise as JSON
+pub fn save(path: impl AsRef<Path>, machine: &Machine, rom: Option<&RomIdentity>) -> Result<()> {
+    save_to(&mut FileStorage, path.as_ref(), machine, rom)
+}
+
+pub fn save_to(storage: &mut dyn Storage, path: &Path, machine: &Machine, rom: Option<&RomIdentity>) -> Result<()> {
+    if path.extension().is_some_and(|extension| extension == STATE_EXTENSION) {
+        storage.write(path, &to_bytes(machine, rom))
+    } else {
+        storage.write(path, to_json(machine, rom)?.as_bytes())
+    }
+}
+
+/// reads a state in either format
+pub fn load(path: impl AsRef<Path>) -> Result<SaveState> {
+    load_from(&FileStorage, path.as_ref())
+}
+
+pub fn load_from(storage: &dyn Storage, path: &Path) -> Result<SaveState> {
+    let bytes = storage.read(path)?;
+    if bytes.starts_with(MAGIC) {
+        return from_bytes(&bytes);
+    }
+    let text = String::from_utf8(bytes).map_err(|_| invalid("not a save state"))?;
+    from_json(&text)
+}
+
+fn invalid(message: &str) -> Error {
+    Error::StateFormatError(String::from(message))
+}
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    // bytes prefixed with their length
+    fn sized(&mut self, bytes: &[u8]) {
+        self.u16(bytes.len() as u16);
+        self.bytes.extend(bytes);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8]> {
+        if count > self.bytes.len() {
+            return Err(invalid("unexpected end of state"));
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn sized(&mut self) -> Result<&'a [u8]> {
+        let count = self.u16()? as usize;
+        self.take(count)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        String::from_utf8(self.sized()?.to_vec()).map_err(|_| invalid("ROM identity is not UTF-8"))
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{Error, Result};
+
+/// where ROMs, save states and other files are read from and written to: local files natively,
+/// memory or the browser's local storage on the web
+pub trait Storage {
+    fn read(&self, path: &Path) -> Result<Vec<u8>>;
+    fn write(&mut self, path: &Path, bytes: &[u8]) -> Result<()>;
+    fn exists(&self, path: &Path) -> bool;
+
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|error| Error::IoError(io::Error::new(io::ErrorKind::InvalidData, error)))
+    }
+}
+
+/// the storage for the platform: local files natively
+#[cfg(not(target_arch = "wasm32"))]
+pub fn platform_storage() -> Box<dyn Storage> {
+    Box::new(FileStorage)
+}
+
+/// the storage for the platform: the browser's local storage on the web (or memory, if the
+/// browser won't give access to it)
+#[cfg(target_arch = "wasm32")]
+pub fn platform_storage() -> Box<dyn Storage> {
+    match BrowserStorage::new() {
+        Some(storage) => Box::new(storage),
+        None => Box::new(MemoryStorage::new()),
+    }
+}
+
+fn not_found(path: &Path) -> Error {
+    Error::IoError(io::Error::new(io::ErrorKind::NotFound, path.display().to_string()))
+}
+
+/// local files, with paths relative to the working directory
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FileStorage;
+
+impl Storage for FileStorage {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        Ok(fs::read(path)?)
+    }
+
+    fn write(&mut self, path: &Path, bytes: &[u8]) -> Result<()> {
+        Ok(fs::write(path, bytes)?)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+}
+
+/// files kept in memory, e.g. ROMs handed over by a web page
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStorage {
+    pub files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        self.files.get(path).cloned().ok_or_else(|| not_found(path))
+    }
+
+    fn write(&mut self, path: &Path, bytes: &[u8]) -> Result<()> {
+        self.files.insert(path.to_path_buf(), bytes.to_vec());
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+}
+
+/// the browser's local storage, with files stored as base64 under their path
+#[cfg(target_arch = "wasm32")]
+pub struct BrowserStorage {
+    storage: web_sys::Storage,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl BrowserStorage {
+    const KEY_PREFIX: &'static str = "chipper8:";
+
+    pub fn new() -> Option<Self> {
+        let storage = web_sys::window()?.local_storage().ok()??;
+        Some(Self { storage })
+    }
+
+    fn key(path: &Path) -> String {
+        format!("{}{}", Self::KEY_PREFIX, path.display())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Storage for BrowserStorage {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        let text = self.storage.get_item(&Self::key(path)).ok().flatten().ok_or_else(|| not_found(path))?;
+        crate::encoding::from_base64(&text)
+    }
+
+    fn write(&mut self, path: &Path, bytes: &[u8]) -> Result<()> {
+        self.storage.set_item(&Self::key(path), &crate::encoding::base64(bytes))
+            .map_err(|_| Error::IoError(io::Error::new(io::ErrorKind::Other, "local storage is full or unavailable")))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.storage.get_item(&Self::key(path)).ok().flatten().is_some()
+    }
+}
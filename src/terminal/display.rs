@@ -0,0 +1,25 @@
+use crate::machine::config;
+
+const UPPER_HALF: char = '▀';
+const LOWER_HALF: char = '▄';
+const FULL_BLOCK: char = '█';
+
+/// renders a display buffer as lines of Unicode half-block characters, so that each character cell
+/// covers two vertically adjacent pixels and the output keeps roughly square pixels
+pub fn render_display(display: &[u8]) -> Vec<String> {
+    let (width, height) = (config::DISPLAY_WIDTH, config::DISPLAY_HEIGHT);
+    let pixel = |x: usize, y: usize| y < height && display.get(x + y * width).is_some_and(|p| *p != 0);
+    let mut lines = Vec::with_capacity(height / 2 + 2);
+    lines.push(format!("┌{}┐", "─".repeat(width)));
+    for y in (0..height).step_by(2) {
+        let row: String = (0..width).map(|x| match (pixel(x, y), pixel(x, y + 1)) {
+            (true, true) => FULL_BLOCK,
+            (true, false) => UPPER_HALF,
+            (false, true) => LOWER_HALF,
+            (false, false) => ' ',
+        }).collect();
+        lines.push(format!("│{}│", row));
+    }
+    lines.push(format!("└{}┘", "─".repeat(width)));
+    lines
+}
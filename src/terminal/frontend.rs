@@ -0,0 +1,68 @@
+use std::io::{self, Stdout, Write};
+
+use crossterm::{cursor, event, execute, queue, terminal};
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+
+use crate::emulator::Emulator;
+use crate::Result;
+use crate::ui::KeyCapture;
+use crate::ui::util::key_capture::KeyBindings;
+
+use super::{render_display, TerminalKeys};
+
+fn is_quit(code: KeyCode, modifiers: KeyModifiers) -> bool {
+    code == KeyCode::Esc || (code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL))
+}
+
+/// runs the emulator in the terminal's alternate screen until the user quits
+pub fn run_emulator(emulator: &mut Emulator) -> Result<()> {
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+    let result = run_loop(emulator, &mut stdout);
+    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn run_loop(emulator: &mut Emulator, stdout: &mut Stdout) -> Result<()> {
+    let mut key_capture = KeyCapture::new();
+    key_capture.bindings = KeyBindings::load_or_default();
+    key_capture.bindings.set_rom(Some(&emulator.rom_name));
+    let mut keys = TerminalKeys::new();
+    let mut last_display = None;
+    loop {
+        if event::poll(emulator.until_next_frame())? {
+            if let Event::Key(key) = event::read()? {
+                if is_quit(key.code, key.modifiers) || emulator.terminated {
+                    return Ok(());
+                }
+                keys.press(&key_capture.bindings, key.code);
+            }
+        }
+        key_capture.update_with(&mut keys);
+        emulator.machine.key_buffer = key_capture.key();
+        let was_terminated = emulator.terminated;
+        emulator.tick();
+        if last_display.as_ref() != Some(&emulator.machine.display) || was_terminated != emulator.terminated {
+            draw(emulator, stdout)?;
+            last_display = Some(emulator.machine.display.clone());
+        }
+    }
+}
+
+fn draw(emulator: &Emulator, stdout: &mut Stdout) -> Result<()> {
+    queue!(stdout, cursor::MoveTo(0, 0))?;
+    for line in render_display(&emulator.machine.display) {
+        write!(stdout, "{}\r\n", line)?;
+    }
+    let status = if emulator.terminated {
+        "machine stopped: press any key to quit"
+    } else {
+        "<ESC> or ^C quits"
+    };
+    queue!(stdout, terminal::Clear(terminal::ClearType::CurrentLine))?;
+    write!(stdout, "{}\r\n", status)?;
+    stdout.flush()?;
+    Ok(())
+}
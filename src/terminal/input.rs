@@ -0,0 +1,67 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::KeyCode;
+use egui::Key;
+
+use crate::input::InputSource;
+use crate::ui::util::key_capture::{KeyBindings, KEYS};
+
+// terminals report key presses (and auto-repeats) but not releases, so a pressed key is treated as
+// held down until this much time has passed without a repeat
+const HOLD_TIME: Duration = Duration::from_millis(150);
+
+fn key_matches(key: Key, code: KeyCode) -> bool {
+    match code {
+        KeyCode::Char(' ') => key == Key::Space,
+        KeyCode::Char(c) => key.name().len() == 1 && key.name().starts_with(c.to_ascii_uppercase()),
+        KeyCode::Enter => key == Key::Enter,
+        KeyCode::Tab => key == Key::Tab,
+        KeyCode::Backspace => key == Key::Backspace,
+        KeyCode::Up => key == Key::ArrowUp,
+        KeyCode::Down => key == Key::ArrowDown,
+        KeyCode::Left => key == Key::ArrowLeft,
+        KeyCode::Right => key == Key::ArrowRight,
+        _ => false,
+    }
+}
+
+/// keypad input from terminal key events, mapped through the active key bindings
+pub struct TerminalKeys {
+    pressed_at: [Option<Instant>; 16],
+}
+
+impl TerminalKeys {
+    pub fn new() -> Self {
+        Self { pressed_at: [None; 16] }
+    }
+
+    /// returns whether the key is bound to a keypad key
+    pub fn press(&mut self, bindings: &KeyBindings, code: KeyCode) -> bool {
+        let binding = bindings.active_binding();
+        match binding.iter().position(|key| key_matches(*key, code)) {
+            Some(index) => {
+                self.pressed_at[KEYS[index] as usize] = Some(Instant::now());
+                true
+            }
+            None => false,
+        }
+    }
+
+}
+
+impl InputSource for TerminalKeys {
+    fn poll(&mut self, _bindings: &KeyBindings, keys: &mut [bool; 16]) {
+        for (pressed, held) in self.pressed_at.iter_mut().zip(keys.iter_mut()) {
+            if pressed.is_some_and(|time| time.elapsed() > HOLD_TIME) {
+                pressed.take();
+            }
+            *held |= pressed.is_some();
+        }
+    }
+}
+
+impl Default for TerminalKeys {
+    fn default() -> Self {
+        Self::new()
+    }
+}
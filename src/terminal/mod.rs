@@ -0,0 +1,9 @@
+pub use display::render_display;
+pub use frontend::run_emulator;
+pub use input::TerminalKeys;
+pub use status::render_status;
+
+mod display;
+mod frontend;
+mod input;
+mod status;
@@ -0,0 +1,45 @@
+use crate::assembler::Symbols;
+use crate::machine::Machine;
+use crate::ui::util::{Address, Byte, Register, Word};
+
+fn address_line(label: &str, address: &crate::machine::Address, machine: &Machine, symbols: Option<&Symbols>) -> String {
+    let word = machine.word_at_address(address).map_or(String::new(), |word| format!("{}", Word::from(word)));
+    let instruction = machine.instruction_at_address(address).map_or(String::new(), |i| format!("{}", i));
+    let line = format!("{:<5} {} {:<6} {:<16}", label, Address::from(address), word, instruction);
+    let Some(symbols) = symbols else { return line; };
+    let index = address.as_index() as u16;
+    let mut line = format!("{} {:<12}", line, symbols.label_at(index).unwrap_or_default());
+    if let (Some(number), Some(source)) = (symbols.line_at(index), symbols.source_at(index)) {
+        line.push_str(&format!(" {}: {}", number, source));
+    }
+    line.trim_end().to_string()
+}
+
+/// renders registers, timers, index, program counter disassembly and the stack as plain text lines,
+/// annotating addresses with labels and source lines when symbols are given
+pub fn render_status(machine: &Machine, symbols: Option<&Symbols>) -> Vec<String> {
+    let mut lines = vec![address_line("PC", &machine.program_counter, machine, symbols)];
+    lines.push(format!(
+        "{:<5} {} {}",
+        "I",
+        Address::from(&machine.index),
+        machine.at_index().map_or(String::new(), |byte| format!("{}", Byte::from(byte))),
+    ));
+    for (row, values) in machine.registers.chunks(8).enumerate() {
+        let cells: Vec<_> = values.iter().enumerate().map(|(column, value)| {
+            format!("{}={}", Register::from(row * 8 + column), Byte::from(*value))
+        }).collect();
+        lines.push(cells.join(" "));
+    }
+    lines.push(format!("DT={} ST={} Key={}",
+                       Byte::from(machine.delay_timer),
+                       Byte::from(machine.sound_timer),
+                       machine.key_buffer.map_or(String::from("none"), |key| format!("{:X}", key))));
+    lines.push(format!("Stack (depth {}):", machine.stack.pointer));
+    for (depth, address) in machine.stack.data.iter().enumerate().take(machine.stack.pointer).rev() {
+        if let Some(address) = address {
+            lines.push(address_line(&format!(" {:01X}", depth), address, machine, symbols));
+        }
+    }
+    lines
+}
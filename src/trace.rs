@@ -0,0 +1,212 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use ringbuffer::{AllocRingBuffer, RingBufferExt, RingBufferWrite};
+
+use crate::{Error, Result};
+use crate::machine::{Instruction, Machine};
+use crate::ui::util::{Address, Byte, Register, Word};
+
+// entries kept for the Trace window
+const TRACE_BUFFER_SIZE: usize = 1024;
+
+/// instruction categories trace entries can be filtered by
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, clap::ValueEnum)]
+pub enum InstructionKind {
+    Exit,
+    Graphics,
+    Flow,
+    Index,
+    Arithmetic,
+    Input,
+    Bcd,
+    Memory,
+}
+
+impl InstructionKind {
+    pub const ALL: [InstructionKind; 8] = [
+        Self::Exit, Self::Graphics, Self::Flow, Self::Index, Self::Arithmetic, Self::Input, Self::Bcd, Self::Memory,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Exit => "Exit",
+            Self::Graphics => "Graphics",
+            Self::Flow => "Flow",
+            Self::Index => "Index",
+            Self::Arithmetic => "Arithmetic",
+            Self::Input => "Input",
+            Self::Bcd => "BCD",
+            Self::Memory => "Memory",
+        }
+    }
+}
+
+impl From<&Instruction> for InstructionKind {
+    fn from(instruction: &Instruction) -> Self {
+        match instruction {
+            Instruction::Exit => Self::Exit,
+            Instruction::Graphics(_) => Self::Graphics,
+            Instruction::Flow(_) => Self::Flow,
+            Instruction::Index { args: _ } => Self::Index,
+            Instruction::Arithmetic { args: _ } => Self::Arithmetic,
+            Instruction::Input(_) => Self::Input,
+            Instruction::BinaryCodedDecimal { args: _ } => Self::Bcd,
+            Instruction::Memory(_) => Self::Memory,
+        }
+    }
+}
+
+/// one executed instruction and the changes it made to the machine
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TraceEntry {
+    pub address: u16,
+    pub opcode: u16,
+    pub instruction: Instruction,
+    // register, value before, value after
+    pub registers: Vec<(usize, u8, u8)>,
+    // index register before and after, if changed
+    pub index: Option<(u16, u16)>,
+    // address, value written
+    pub memory_writes: Vec<(u16, u8)>,
+}
+
+impl Display for TraceEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {:<16}", Address::from(self.address), Word::from(self.opcode), self.instruction.to_string())?;
+        for (register, before, after) in &self.registers {
+            write!(f, " {}: {}→{}", Register::from(*register), Byte::from(*before), Byte::from(*after))?;
+        }
+        if let Some((before, after)) = self.index {
+            write!(f, " I: {}→{}", Address::from(before), Address::from(after))?;
+        }
+        for (address, value) in &self.memory_writes {
+            write!(f, " [{}]={}", Address::from(*address), Byte::from(*value))?;
+        }
+        Ok(())
+    }
+}
+
+/// which instructions get traced: all of them if no ranges or kinds are given
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TraceFilter {
+    pub ranges: Vec<Range<u16>>,
+    pub kinds: BTreeSet<InstructionKind>,
+}
+
+impl TraceFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn matches(&self, address: u16, instruction: &Instruction) -> bool {
+        (self.ranges.is_empty() || self.ranges.iter().any(|range| range.contains(&address)))
+            && (self.kinds.is_empty() || self.kinds.contains(&InstructionKind::from(instruction)))
+    }
+
+    /// parses an address range given as `START..END` (hex, end exclusive)
+    pub fn parse_range(text: &str) -> Result<Range<u16>> {
+        let error = || Error::SyntaxError(format!("expected an address range like 0x200..0x300, found `{}`", text));
+        let (start, end) = text.split_once("..").ok_or_else(error)?;
+        let parse = |value: &str| {
+            let value = value.trim();
+            u16::from_str_radix(value.strip_prefix("0x").unwrap_or(value), 16).map_err(|_| error())
+        };
+        Ok(parse(start)?..parse(end)?)
+    }
+}
+
+/// records executed instructions to a ring buffer (for the Trace window) and/or a writer (e.g. a file)
+pub struct Tracer {
+    pub filter: TraceFilter,
+    pub buffer: Option<AllocRingBuffer<TraceEntry>>,
+    writer: Option<Box<dyn Write>>,
+}
+
+impl Tracer {
+    pub fn new() -> Self {
+        Self { filter: TraceFilter::new(), buffer: None, writer: None }
+    }
+
+    /// keeps the most recent entries in memory
+    pub fn with_buffer(mut self) -> Self {
+        self.buffer = Some(AllocRingBuffer::with_capacity(TRACE_BUFFER_SIZE));
+        self
+    }
+
+    pub fn with_writer(mut self, writer: Box<dyn Write>) -> Self {
+        self.writer = Some(writer);
+        self
+    }
+
+    pub fn with_stdout(self) -> Self {
+        self.with_writer(Box::new(io::stdout()))
+    }
+
+    pub fn with_file(self, path: impl AsRef<Path>) -> Result<Self> {
+        Ok(self.with_writer(Box::new(BufWriter::new(File::create(path)?))))
+    }
+
+    pub fn with_filter(mut self, filter: TraceFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn clear(&mut self) {
+        if let Some(buffer) = &mut self.buffer {
+            buffer.clear();
+        }
+    }
+
+    /// runs one machine tick, recording the instruction executed if it passes the filter
+    pub fn tick(&mut self, machine: &mut Machine) -> Result<()> {
+        let address = u16::from(&machine.program_counter);
+        let instruction = machine.next_instruction()?;
+        if !self.filter.matches(address, &instruction) {
+            return Ok(machine.tick()?);
+        }
+        let opcode = machine.at_program_counter().unwrap_or_default();
+        let registers = machine.registers.clone();
+        let index = u16::from(&machine.index);
+        let memory = machine.memory.clone();
+        machine.tick()?;
+        let entry = TraceEntry {
+            address,
+            opcode,
+            registers: registers.iter().zip(&machine.registers).enumerate()
+                .filter(|(_, (before, after))| before != after)
+                .map(|(register, (before, after))| (register, *before, *after))
+                .collect(),
+            index: Some((index, u16::from(&machine.index))).filter(|(before, after)| before != after),
+            memory_writes: memory.iter().zip(&machine.memory).enumerate()
+                .filter(|(_, (before, after))| before != after)
+                .map(|(address, (_, after))| (address as u16, *after))
+                .collect(),
+            instruction,
+        };
+        if let Some(writer) = &mut self.writer {
+            writeln!(writer, "{}", entry)?;
+        }
+        if let Some(buffer) = &mut self.buffer {
+            buffer.push(entry);
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        if let Some(writer) = &mut self.writer {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Tracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,10 +1,12 @@
-use egui::{Color32, Label, Slider, Ui};
+use egui::{Color32, DragValue, Label, Slider, Ui};
 use egui::widget_text::RichText;
 
 use input::Input;
 
-use crate::command::{Command, MetaCommand};
+use crate::command::{Command, Location, MetaCommand};
+use crate::machine;
 use crate::machine::Machine;
+use crate::settings::Settings;
 use crate::ui::State;
 use crate::ui::util::Nibble;
 use crate::ui::util::table::TabularData;
@@ -14,12 +16,15 @@ mod input;
 
 pub struct BottomBar {
     input: Input,
+    // instructions executed by the run button
+    run_ticks: usize,
 }
 
 impl BottomBar {
     pub fn new() -> Self {
         Self {
             input: Input::new(),
+            run_ticks: 100,
         }
     }
 
@@ -27,11 +32,33 @@ impl BottomBar {
     pub fn ui(&mut self, ui: &mut Ui, machine: &Machine, state: &mut State) {
         ui.horizontal(|ui| {
             ui.label("Machine Tick Rate: ");
-            ui.add(Slider::new(&mut state.frames_per_second, 1..=120));
+            ui.add(Slider::new(&mut state.frames_per_second, 1..=Settings::MAX_FRAMES_PER_SECOND));
+            ui.add(Slider::new(&mut state.speed, 0.1..=10.0).logarithmic(true).text("Speed"));
+            ui.checkbox(&mut state.turbo, "Turbo");
             ui.checkbox(&mut state.running, "Running");
             ui.checkbox(&mut state.skip_unknown_opcode, "Skip Unknown Opcode");
+            let mut command = None;
             if ui.button("⏩").on_hover_text("Next Instruction").clicked() {
-                state.command_buffer = Some(Command::Meta(MetaCommand::Tick));
+                command = Some(MetaCommand::Tick);
+            }
+            if ui.button("⤼").on_hover_text("Step Over (run calls to completion)").clicked() {
+                command = Some(MetaCommand::Next);
+            }
+            let in_subroutine = machine.stack.pointer > 0;
+            if ui.add_enabled(in_subroutine, egui::Button::new("⤴")).on_hover_text("Step Out").clicked() {
+                command = Some(MetaCommand::Finish);
+            }
+            // the cursor is the address picked in the disassembly or call stack windows
+            let cursor = state.disassembly_focus.and_then(|address| machine::Address::try_from(address).ok());
+            if ui.add_enabled(cursor.is_some(), egui::Button::new("⇥")).on_hover_text("Run to Cursor").clicked() {
+                command = cursor.map(|address| MetaCommand::Until(Location::Address(address)));
+            }
+            if ui.button("▶ n").on_hover_text("Run n Instructions").clicked() {
+                command = Some(MetaCommand::Run(self.run_ticks));
+            }
+            ui.add(DragValue::new(&mut self.run_ticks).clamp_range(1..=100_000));
+            if let Some(command) = command {
+                state.command_buffer = Some(Command::Meta(command));
             }
             ui.separator();
             self.input.ui(ui, state);
@@ -44,7 +71,7 @@ impl BottomBar {
             ui.separator();
             ui.label("Program Counter");
             // todo: dependent on table UI implementation, subject to change
-            let helper = ProgramCounterHelper { machine };
+            let helper = ProgramCounterHelper { machine, symbols: state.symbols() };
             for label in helper.rows().into_iter().next().unwrap() {
                 ui.label(label.monospace());
             }
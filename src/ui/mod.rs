@@ -1,8 +1,10 @@
+use std::collections::BTreeMap;
+
 use egui::{Align, Checkbox, Context, Layout, Sense};
 
 use bottom_bar::BottomBar;
 pub use command_history::CommandHistory;
-pub use state::{MemoryTag, Rom, State};
+pub use state::{MemoryTag, Rom, State, StopCondition};
 pub use util::key_capture::KeyCapture;
 use windows::Window;
 
@@ -27,6 +29,18 @@ impl Ui {
         }
     }
 
+    pub fn open_windows(&self) -> BTreeMap<String, bool> {
+        self.windows.iter().map(|window| (String::from(window.name()), window.open)).collect()
+    }
+
+    pub fn set_open_windows(&mut self, open_windows: &BTreeMap<String, bool>) {
+        for window in &mut self.windows {
+            if let Some(open) = open_windows.get(window.name()) {
+                window.open = *open;
+            }
+        }
+    }
+
     pub fn draw(&mut self, ctx: &Context, machine: &Machine, state: &mut State) {
         egui::TopBottomPanel::bottom("bar").show(ctx, |ui| {
             self.bottom_bar.ui(ui, machine, state);
@@ -1,21 +1,46 @@
-use std::collections::BTreeMap;
-use std::fs;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ops::Range;
 use std::path::Path;
 use std::time::Duration;
 
 use egui::Color32;
+use egui::ecolor::Hsva;
 
 use crate::{Error, Result};
-use crate::assembler::Tokens;
-use crate::command::Command;
-use crate::machine;
+use crate::assembler::{self, Symbols, Tokens};
+use crate::clock::{FrameClock, Instant};
+use crate::command::{Command, Location};
+use crate::gdb::GdbServer;
+use crate::library::{OctoCartridge, Platform, RomDatabase, RomFormat, RomInfo, RomOptions};
+use crate::machine::{self, Address, Machine};
+use crate::settings::{DisplayOptions, Palette, Settings, UserTag};
+use crate::storage::{FileStorage, Storage};
+use crate::trace::Tracer;
 
 use super::command_history::CommandHistory;
 use super::KeyCapture;
 
+/// when the VM main loop should pause by itself, in addition to breakpoints
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StopCondition {
+    // the call stack is no deeper than this (so the subroutine running at the time has returned)
+    StackDepth(usize),
+    // the program counter reaches an address
+    Address(u16),
+    // the program counter reaches an address with the call stack no deeper than `depth` (so recursive
+    // calls passing through the same address don't count)
+    Return { address: u16, depth: usize },
+    // this many more instructions have been executed
+    Ticks(usize),
+}
+
 pub struct State {
     pub running: bool,
+    pub stop_condition: Option<StopCondition>,
+    // paces the VM main loop
+    pub clock: FrameClock,
+    // 60Hz frames run by the VM main loop so far
+    pub frames: u64,
     pub skip_unknown_opcode: bool,
     pub command_history: CommandHistory,
     pub command_buffer: Option<Command>,
@@ -26,12 +51,38 @@ pub struct State {
     pub rom: Option<Rom>,
     pub memory_tags: BTreeMap<MemoryTag, Range<usize>>,
     pub frames_per_second: u64,
+    // multiplies the instructions executed per frame
+    pub speed: f64,
+    // run as fast as possible instead of in real time
+    pub turbo: bool,
+    pub display: DisplayOptions,
+    // ROM name -> display options overriding `display` while that ROM is loaded
+    pub rom_display: BTreeMap<String, DisplayOptions>,
+    // name or path the current ROM was loaded from, remembered between runs
+    pub last_rom: Option<String>,
+    // directories scanned by the ROM library
+    pub rom_directories: Vec<String>,
+    // addresses the VM main loop pauses at
+    pub breakpoints: BTreeSet<u16>,
+    // ROM name -> memory tags added with `:tag`
+    pub rom_tags: BTreeMap<String, Vec<UserTag>>,
+    // memory tag name -> colour replacing the tag's default colour
+    pub tag_colors: BTreeMap<String, Color32>,
+    // records instructions executed by the VM main loop, when tracing is on
+    pub tracer: Option<Tracer>,
+    // address the disassembly window shows, instead of following the program counter
+    pub disassembly_focus: Option<u16>,
+    // debugger server started with `:gdb`
+    pub gdb: Option<GdbServer>,
 }
 
 impl State {
     pub fn new() -> Self {
         Self {
             running: false,
+            stop_condition: None,
+            clock: FrameClock::new(),
+            frames: 0,
             skip_unknown_opcode: false,
             command_history: CommandHistory::new(),
             command_buffer: None,
@@ -46,6 +97,146 @@ impl State {
             // todo: is this really state or should it be machine 'config'?
             // (but for now the UI can't modify the machine directly so it lives here)
             frames_per_second: 60,
+            speed: 1.0,
+            turbo: false,
+            display: DisplayOptions::new(),
+            rom_display: BTreeMap::new(),
+            last_rom: None,
+            rom_directories: vec![String::from("roms")],
+            breakpoints: BTreeSet::new(),
+            rom_tags: BTreeMap::new(),
+            tag_colors: BTreeMap::new(),
+            tracer: None,
+            disassembly_focus: None,
+            gdb: None,
+        }
+    }
+
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.frames_per_second = settings.frames_per_second.clamp(1, Settings::MAX_FRAMES_PER_SECOND);
+        self.skip_unknown_opcode = settings.skip_unknown_opcode;
+        self.display = settings.display;
+        self.rom_display = settings.rom_display.clone();
+        self.last_rom = settings.last_rom.clone();
+        self.rom_directories = settings.rom_directories.clone();
+        self.rom_tags = settings.rom_tags.clone();
+        self.tag_colors = settings.tag_colors.clone();
+        self.update_rom_tags();
+    }
+
+    /// settings reflecting the current state (other than window layout, which the UI owns)
+    pub fn settings(&self) -> Settings {
+        Settings {
+            frames_per_second: self.frames_per_second,
+            skip_unknown_opcode: self.skip_unknown_opcode,
+            display: self.display,
+            rom_display: self.rom_display.clone(),
+            last_rom: self.last_rom.clone(),
+            rom_directories: self.rom_directories.clone(),
+            rom_tags: self.rom_tags.clone(),
+            tag_colors: self.tag_colors.clone(),
+            ..Settings::new()
+        }
+    }
+
+    /// display options in effect for the loaded ROM
+    pub fn display_options(&self) -> DisplayOptions {
+        self.rom.as_ref()
+            .and_then(|rom| self.rom_display.get(&rom.name))
+            .copied()
+            .unwrap_or(self.display)
+    }
+
+    /// display options to edit: the loaded ROM's own options if it has any, otherwise the defaults
+    pub fn display_options_mut(&mut self) -> &mut DisplayOptions {
+        match &self.rom {
+            Some(rom) if self.rom_display.contains_key(&rom.name) => self.rom_display.get_mut(&rom.name).unwrap(),
+            _ => &mut self.display,
+        }
+    }
+
+    pub fn rom_display_override(&self) -> bool {
+        self.rom.as_ref().is_some_and(|rom| self.rom_display.contains_key(&rom.name))
+    }
+
+    /// gives the loaded ROM its own display options (starting from the current ones) or removes them
+    pub fn set_rom_display_override(&mut self, enabled: bool) {
+        let Some(rom) = &self.rom else { return; };
+        if enabled {
+            self.rom_display.entry(rom.name.clone()).or_insert(self.display);
+        } else {
+            self.rom_display.remove(&rom.name);
+        }
+    }
+
+    /// symbols of the loaded ROM, if it has any
+    pub fn symbols(&self) -> Option<&Symbols> {
+        self.rom.as_ref()?.symbols.as_ref()
+    }
+
+    /// the address of a location, looking labels up in the loaded ROM's symbols
+    pub fn resolve(&self, location: &Location) -> Result<u16> {
+        match location {
+            Location::Address(address) => Ok(address.as_index() as u16),
+            Location::Label(label) => self.symbols()
+                .and_then(|symbols| symbols.address_of(label))
+                .ok_or_else(|| Error::UnknownLabel(label.clone())),
+        }
+    }
+
+    /// describes an address by its label and source line, when symbols are loaded
+    pub fn describe(&self, address: u16) -> Option<String> {
+        let symbols = self.symbols()?;
+        let label = symbols.label_at(address);
+        let line = symbols.line_at(address).map(|line| match symbols.source_at(address) {
+            Some(source) => format!("line {}: {}", line, source),
+            None => format!("line {}", line),
+        });
+        match (label, line) {
+            (Some(label), Some(line)) => Some(format!("{} ({})", label, line)),
+            (label, line) => label.or(line),
+        }
+    }
+
+    pub fn tag_color(&self, tag: &MemoryTag) -> Color32 {
+        self.tag_colors.get(&tag.name()).copied().unwrap_or_else(|| tag.color())
+    }
+
+    /// tags the loaded ROM's memory, replacing any existing tag of the same name
+    pub fn add_user_tag(&mut self, tag: UserTag) -> Result<()> {
+        let rom = self.rom.as_ref().ok_or_else(|| Error::MissingArgument(String::from("a ROM must be loaded to tag its memory")))?;
+        let tags = self.rom_tags.entry(rom.name.clone()).or_default();
+        tags.retain(|existing| existing.name != tag.name);
+        tags.push(tag);
+        self.update_rom_tags();
+        Ok(())
+    }
+
+    pub fn remove_user_tag(&mut self, name: &str) -> Result<()> {
+        let tags = self.rom.as_ref().and_then(|rom| self.rom_tags.get_mut(&rom.name));
+        let Some(tags) = tags.filter(|tags| tags.iter().any(|tag| tag.name == name)) else {
+            return Err(Error::MissingArgument(format!("no memory tag named `{}`", name)));
+        };
+        tags.retain(|tag| tag.name != name);
+        self.update_rom_tags();
+        Ok(())
+    }
+
+    /// recreates the memory tags belonging to the loaded ROM: its labels and user-defined tags
+    pub fn update_rom_tags(&mut self) {
+        self.memory_tags.retain(|tag, _| !matches!(tag, MemoryTag::Label { .. } | MemoryTag::User { .. }));
+        let Some(rom) = &self.rom else { return; };
+        if let (Some(symbols), Some(range)) = (&rom.symbols, rom.loaded_range()) {
+            // each label covers memory up to the next label (or the end of the program)
+            let mut labels: Vec<_> = symbols.labels.iter().map(|(name, address)| (*address as usize, name)).collect();
+            labels.sort();
+            for (index, (start, name)) in labels.iter().enumerate() {
+                let end = labels.get(index + 1).map_or(range.end, |(next, _)| *next).max(*start);
+                self.memory_tags.insert(MemoryTag::Label { name: (*name).clone() }, *start..end);
+            }
+        }
+        for tag in self.rom_tags.get(&rom.name).into_iter().flatten() {
+            self.memory_tags.insert(MemoryTag::User { name: tag.name.clone() }, tag.start as usize..tag.end as usize);
         }
     }
 
@@ -53,6 +244,18 @@ impl State {
         Duration::from_nanos(1_000_000_000 / self.frames_per_second)
     }
 
+    pub fn instructions_per_second(&self) -> f64 {
+        self.frames_per_second as f64 * self.speed
+    }
+
+    /// how long front-ends can wait before running frames again
+    pub fn until_next_frame(&self) -> Duration {
+        if self.turbo {
+            return Duration::ZERO;
+        }
+        self.clock.until_next_frame(Instant::now())
+    }
+
     pub fn parse_command(&mut self, input: &str) {
         let tokens = Tokens::from(input);
         match tokens.try_into() {
@@ -70,12 +273,24 @@ impl State {
 
     pub fn load_rom(&mut self, rom: Rom) {
         self.memory_tags.insert(MemoryTag::UserProgram { name: rom.name.clone() }, rom.loaded_range().unwrap());
+        // colours embedded in the ROM become its display options, unless it already has its own
+        if let Some(colors) = rom.options.colors {
+            self.rom_display.entry(rom.name.clone()).or_insert(DisplayOptions {
+                palette: Palette::Custom,
+                custom_colors: colors,
+                ..self.display
+            });
+        }
+        self.key_capture.bindings.set_rom(Some(&rom.name));
         self.rom = Some(rom);
+        self.update_rom_tags();
     }
 
     pub fn unload_rom(&mut self) -> Option<Rom> {
         let rom = self.rom.take()?;
         self.memory_tags.remove(&MemoryTag::UserProgram { name: rom.name.clone() });
+        self.key_capture.bindings.set_rom(None);
+        self.update_rom_tags();
         Some(rom)
     }
 
@@ -91,6 +306,11 @@ pub enum MemoryTag {
     Reserved,
     SystemFont,
     UserProgram { name: String },
+    // the memory from a label in the ROM's symbols up to the next label
+    Label { name: String },
+    // added by the user with `:tag`
+    User { name: String },
+    Breakpoint { address: u16 },
     Index,
     ProgramCounter,
 }
@@ -101,6 +321,9 @@ impl MemoryTag {
             Self::Reserved => Color32::LIGHT_GRAY,
             Self::SystemFont => Color32::YELLOW,
             Self::UserProgram { name: _name } => Color32::RED,
+            Self::Label { name } => name_color(name, 0.3),
+            Self::User { name } => name_color(name, 0.7),
+            Self::Breakpoint { address: _address } => Color32::LIGHT_BLUE,
             Self::ProgramCounter => Color32::WHITE,
             Self::Index => Color32::LIGHT_GREEN,
         }
@@ -111,27 +334,119 @@ impl MemoryTag {
             Self::Reserved => String::from("System Reserved"),
             Self::SystemFont => String::from("System Fonts"),
             Self::UserProgram { name } => format!("User Program ({}.rom)", name),
+            Self::Label { name } => format!("Label {}", name),
+            Self::User { name } => name.clone(),
+            Self::Breakpoint { address: _address } => String::from("Breakpoint"),
             Self::ProgramCounter => String::from("Program Counter"),
             Self::Index => String::from("Index"),
         }
     }
 }
 
+// a colour picked by hashing a tag name, so the same name always gets the same colour
+fn name_color(name: &str, saturation: f32) -> Color32 {
+    let hash = name.bytes().fold(0x811C9DC5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193));
+    Hsva::new((hash % 360) as f32 / 360.0, saturation, 1.0, 1.0).into()
+}
+
 // todo: clearly ROM doesn't belong in this module
 pub struct Rom {
     pub name: String,
     pub bytes: Vec<u8>,
     pub loaded_at: Option<usize>,
+    // metadata from the ROM database, if the ROM is known
+    pub info: Option<RomInfo>,
+    pub options: RomOptions,
+    // labels and source lines, for ROMs assembled from Octo source
+    pub symbols: Option<Symbols>,
+    // problems with files next to the ROM, which it was loaded without
+    pub warnings: Vec<String>,
 }
 
 impl Rom {
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
-        let bytes = fs::read(path.as_ref())?;
-        Ok(Self {
-            name: String::from(path.as_ref().file_name().unwrap().to_str().unwrap()),
+        Self::from_storage(&FileStorage, path)
+    }
+
+    /// loads a ROM in any of the formats `from_file` understands from `storage`
+    pub fn from_storage(storage: &dyn Storage, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut warnings = Vec::new();
+        let (bytes, options, symbols) = match RomFormat::from_path(path) {
+            RomFormat::Binary(platform) => {
+                // a symbol map saved next to the ROM by the assembler: a stale or broken one
+                // shouldn't stop the ROM from loading
+                let symbols_path = Symbols::path_for(path);
+                let symbols = match storage.exists(&symbols_path).then(|| Symbols::load_from(storage, &symbols_path)) {
+                    Some(Ok(symbols)) => Some(symbols),
+                    Some(Err(error)) => {
+                        warnings.push(format!("ignored symbols in '{}': {}", symbols_path.display(), error));
+                        None
+                    }
+                    None => None,
+                };
+                (storage.read(path)?, RomOptions::new(platform), symbols)
+            }
+            RomFormat::OctoCartridge => {
+                let cartridge = OctoCartridge::decode(&storage.read(path)?)?;
+                let program = assembler::compile(&cartridge.program)?;
+                let symbols = Symbols::new(&program, None, &cartridge.program);
+                (program.bytes, cartridge.rom_options(), Some(symbols))
+            }
+            RomFormat::OctoSource => {
+                let source = storage.read_to_string(path)?;
+                let program = assembler::compile(&source)?;
+                let symbols = Symbols::new(&program, Some(path.to_path_buf()), &source);
+                (program.bytes, RomOptions::new(Platform::Chip8), Some(symbols))
+            }
+        };
+        let name = String::from(path.file_name().unwrap().to_str().unwrap());
+        Ok(Self { warnings, ..Self::new(name, bytes, options, symbols) })
+    }
+
+    /// a binary CHIP-8 ROM held in memory, e.g. one embedded in another program
+    pub fn from_bytes(name: impl Into<String>, bytes: Vec<u8>) -> Self {
+        Self::new(name.into(), bytes, RomOptions::new(Platform::Chip8), None)
+    }
+
+    fn new(name: String, bytes: Vec<u8>, mut options: RomOptions, symbols: Option<Symbols>) -> Self {
+        let info = RomDatabase::bundled().lookup(&bytes).cloned();
+        // the database knows better than file extensions and embedded options
+        if let Some(info) = &info {
+            options.platform = info.platform;
+            options.quirks.merge(&info.quirks);
+        }
+        Self {
+            name,
             bytes,
             loaded_at: None,
-        })
+            info,
+            options,
+            symbols,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// loads the ROM into the machine's memory (at 0x200 unless given an address), with the
+    /// quirks it needs
+    pub fn load(&mut self, machine: &mut Machine, address: Option<&Address>) {
+        if self.loaded_at.is_some() {
+            panic!("rom already loaded");
+        }
+        let default_load_address = Address::try_from(0x200u16).unwrap();
+        let address = address.unwrap_or(&default_load_address);
+        self.loaded_at = Some(usize::from(address));
+        self.options.quirks.apply(&mut machine.config);
+        machine.load_program(address, &self.bytes);
+    }
+
+    pub fn unload(&mut self, machine: &mut Machine) {
+        if self.loaded_at.is_none() {
+            panic!("attempt to unload ROM that was never loaded");
+        }
+        machine.memory[self.loaded_range().unwrap()].fill(0);
+        self.loaded_at = None;
+        // todo: should we move program counter?
     }
 
     pub fn loaded_range(&self) -> Option<Range<usize>> {
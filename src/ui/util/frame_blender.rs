@@ -0,0 +1,93 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq, clap::ValueEnum)]
+pub enum BlendMode {
+    // show the framebuffer exactly as the machine left it
+    Raw,
+    // pixels switched off fade out over the following frames
+    Decay,
+    // a pixel is on if it was on in any of the last few frames
+    Or,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 3] = [Self::Raw, Self::Decay, Self::Or];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Raw => "Raw framebuffer",
+            Self::Decay => "Decay",
+            Self::Or => "OR of last frames",
+        }
+    }
+}
+
+/// post-processing stage between the machine display and the image builder: CHIP-8 games erase and
+/// redraw sprites every frame, so blending consecutive frames reduces flicker
+pub struct FrameBlender {
+    pub mode: BlendMode,
+    // fraction of brightness kept per frame (decay mode)
+    pub persistence: f32,
+    // number of frames combined (OR mode)
+    pub frames: usize,
+    history: VecDeque<Vec<u8>>,
+    output: Vec<u8>,
+    // emulated frame last fed through, see `update`
+    last_frame: Option<u64>,
+}
+
+impl FrameBlender {
+    pub const MAX_FRAMES: usize = 8;
+
+    pub fn new(size: usize) -> Self {
+        Self {
+            mode: BlendMode::Raw,
+            persistence: 0.5,
+            frames: 2,
+            history: VecDeque::with_capacity(Self::MAX_FRAMES),
+            output: vec![0; size],
+            last_frame: None,
+        }
+    }
+
+    /// feeds the display through the blender once per emulated frame (`frame` counts them), or when
+    /// it changed without a frame being run, e.g. when single-stepping; redraws in between (say, as
+    /// the mouse moves) get the same pixel intensities, so blending runs at the emulation's pace
+    pub fn update(&mut self, frame: u64, display: &[u8]) -> &[u8] {
+        let changed = self.history.front().is_none_or(|last| last.as_slice() != display);
+        if self.last_frame != Some(frame) || changed {
+            self.last_frame = Some(frame);
+            return self.blend(display);
+        }
+        &self.output
+    }
+
+    /// feeds the next frame through the blender, returning the pixel intensities to draw
+    pub fn blend(&mut self, frame: &[u8]) -> &[u8] {
+        self.output.resize(frame.len(), 0);
+        match self.mode {
+            BlendMode::Raw => self.output.copy_from_slice(frame),
+            BlendMode::Decay => {
+                for (output, pixel) in self.output.iter_mut().zip(frame) {
+                    *output = (*pixel).max((*output as f32 * self.persistence) as u8);
+                }
+            }
+            BlendMode::Or => {
+                self.output.copy_from_slice(frame);
+                for previous in self.history.iter().take(self.frames.saturating_sub(1)) {
+                    for (output, pixel) in self.output.iter_mut().zip(previous) {
+                        *output = (*output).max(*pixel);
+                    }
+                }
+            }
+        };
+        // keep the history up to date whatever the mode, so switching mode takes effect immediately
+        if self.history.len() == Self::MAX_FRAMES {
+            self.history.pop_back();
+        }
+        self.history.push_front(frame.to_vec());
+        &self.output
+    }
+}
@@ -5,6 +5,12 @@ pub struct ImageBuilder {
     pub height: usize,
     pub pixel_size: usize,
     pub color_map: Vec<Color32>,
+    // fills empty images
+    pub background: Color32,
+    // colour dim pixels fade to, letting whatever is behind the image show through by default
+    pub unlit: Color32,
+    // darken the bottom row of each pixel to imitate CRT scanlines
+    pub scanlines: bool,
 }
 
 impl ImageBuilder {
@@ -16,11 +22,14 @@ impl ImageBuilder {
             height,
             pixel_size: 4,
             color_map,
+            background: Color32::BLACK,
+            unlit: Color32::TRANSPARENT,
+            scanlines: false,
         }
     }
 
     pub fn build_empty(&self) -> ColorImage {
-        ColorImage::new(self.pixel_size(), Color32::BLACK)
+        ColorImage::new(self.pixel_size(), self.background)
     }
 
     pub fn build_from_memory(&self, memory: &[u8], force_on: Vec<usize>) -> ColorImage {
@@ -37,13 +46,19 @@ impl ImageBuilder {
                 self.set_pixel(
                     &mut image,
                     &[x, y],
-                    self.color_map[index].linear_multiply(scale),
+                    mix(self.unlit, self.color_map[index], scale),
                 );
             }
         };
         image
     }
 
+    pub fn set_colors(&mut self, foreground: Color32, background: Color32) {
+        self.color_map.fill(foreground);
+        self.background = background;
+        self.unlit = background;
+    }
+
     pub fn size(&self) -> usize { self.width * self.height }
     fn pixel_width(&self) -> usize { self.width * self.pixel_size }
     fn pixel_height(&self) -> usize { self.height * self.pixel_size }
@@ -63,7 +78,13 @@ impl ImageBuilder {
     }
 
     fn set_pixel(&self, image: &mut ColorImage, pos: &[usize; 2], color: Color32) {
+        let scanline = mix(self.unlit, color, 0.4);
         for j in 0..self.pixel_size {
+            let color = if self.scanlines && self.pixel_size > 1 && j == self.pixel_size - 1 {
+                scanline
+            } else {
+                color
+            };
             for i in 0..self.pixel_size {
                 image.pixels[self.memory_offset(pos, &[i, j])] = color;
             }
@@ -71,3 +92,15 @@ impl ImageBuilder {
     }
 }
 
+
+// linear interpolation between two (premultiplied) colours
+fn mix(from: Color32, to: Color32, t: f32) -> Color32 {
+    let [r0, g0, b0, a0] = from.linear_multiply(1.0 - t).to_array();
+    let [r1, g1, b1, a1] = to.linear_multiply(t).to_array();
+    Color32::from_rgba_premultiplied(
+        r0.saturating_add(r1),
+        g0.saturating_add(g1),
+        b0.saturating_add(b1),
+        a0.saturating_add(a1),
+    )
+}
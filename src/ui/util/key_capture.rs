@@ -1,6 +1,13 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
 
 use egui::{Key, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::input::{self, Button, ButtonMap, InputSource};
+use crate::Result;
+use crate::settings;
 
 // key layout of the original COSMAC VIP as well as most contemporary emulators
 pub const KEYS: [u8; 16] = [
@@ -10,16 +17,37 @@ pub const KEYS: [u8; 16] = [
     0xA, 0x0, 0xB, 0xF,
 ];
 
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct KeyBindings {
     pub active: String,
-    bindings: HashMap<String, [Key; 16]>,
+    bindings: BTreeMap<String, [Key; 16]>,
+    // keymap to use instead of the active one while a particular ROM (by name) is loaded
+    #[serde(default)]
+    rom_overrides: BTreeMap<String, String>,
+    // gamepad button mapping, with optional per-ROM replacements
+    #[serde(default = "input::default_button_map")]
+    button_map: ButtonMap,
+    #[serde(default)]
+    rom_button_maps: BTreeMap<String, ButtonMap>,
+    #[serde(skip)]
+    rom: Option<String>,
 }
 
 impl KeyBindings {
-    fn new() -> Self {
+    /// the built-in keymaps, without reading the user config file
+    pub fn new() -> Self {
         Self {
             active: String::from("Moonlander"),
-            bindings: HashMap::from([
+            bindings: Self::presets(),
+            rom_overrides: BTreeMap::new(),
+            button_map: input::default_button_map(),
+            rom_button_maps: BTreeMap::new(),
+            rom: None,
+        }
+    }
+
+    fn presets() -> BTreeMap<String, [Key; 16]> {
+        BTreeMap::from([
                 (String::from("Default"), [
                     Key::Num1,
                     Key::Num2,
@@ -57,12 +85,132 @@ impl KeyBindings {
                     Key::V,
                     Key::B,
                 ], ),
-            ]),
+            ])
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        Some(settings::config_dir()?.join("key_bindings.json"))
+    }
+
+    /// loads key bindings from the user config file, falling back to the built-in keymaps if there
+    /// is no config file yet (built-in keymaps missing from the file are restored)
+    pub fn load() -> Result<Self> {
+        let mut bindings = match Self::path() {
+            Some(path) if path.exists() => serde_json::from_str(&fs::read_to_string(path)?)?,
+            _ => Self::new(),
+        };
+        for (name, keys) in Self::presets() {
+            bindings.bindings.entry(name).or_insert(keys);
+        }
+        if !bindings.bindings.contains_key(&bindings.active) {
+            bindings.active = Self::new().active;
+        }
+        Ok(bindings)
+    }
+
+    pub fn load_or_default() -> Self {
+        Self::load().unwrap_or_else(|error| {
+            eprintln!("Error loading key bindings, using defaults: {}", error);
+            Self::new()
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(path) = Self::path() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, serde_json::to_string_pretty(self)?)?;
         }
+        Ok(())
+    }
+
+    /// name of the keymap currently in use, taking ROM overrides into account
+    pub fn effective(&self) -> &String {
+        self.rom.as_ref()
+            .and_then(|rom| self.rom_overrides.get(rom))
+            .filter(|name| self.bindings.contains_key(*name))
+            .unwrap_or(&self.active)
     }
 
     pub fn active_binding(&self) -> &[Key; 16] {
-        &self.bindings[&self.active]
+        &self.bindings[self.effective()]
+    }
+
+    /// gamepad button mapping currently in use, taking ROM overrides into account
+    pub fn button_map(&self) -> &ButtonMap {
+        self.rom.as_ref()
+            .and_then(|rom| self.rom_button_maps.get(rom))
+            .unwrap_or(&self.button_map)
+    }
+
+    pub fn has_rom_button_map(&self) -> bool {
+        self.rom.as_ref().is_some_and(|rom| self.rom_button_maps.contains_key(rom))
+    }
+
+    /// gives the current ROM its own gamepad button mapping (starting as a copy of the general
+    /// one), or removes it
+    pub fn set_rom_button_map(&mut self, enabled: bool) {
+        if let Some(rom) = &self.rom {
+            if enabled {
+                self.rom_button_maps.entry(rom.clone()).or_insert_with(|| self.button_map.clone());
+            } else {
+                self.rom_button_maps.remove(rom);
+            }
+        }
+    }
+
+    /// maps a gamepad button to a CHIP-8 key value (or to nothing) in the button mapping currently
+    /// in use
+    pub fn bind_button(&mut self, button: Button, key: Option<u8>) {
+        let map = match self.rom.as_ref().and_then(|rom| self.rom_button_maps.get_mut(rom)) {
+            Some(map) => map,
+            None => &mut self.button_map,
+        };
+        match key {
+            Some(key) => map.insert(button, key),
+            None => map.remove(&button),
+        };
+    }
+
+    /// rebinds a keypad key (by its index in `KEYS`) in the keymap currently in use
+    pub fn rebind(&mut self, index: usize, key: Key) {
+        let name = self.effective().clone();
+        if let Some(binding) = self.bindings.get_mut(&name) {
+            binding[index] = key;
+        }
+    }
+
+    /// copies the keymap currently in use under a new name and makes that the active keymap; if a
+    /// keymap already has the name, a number is added to it. Returns the name used
+    pub fn duplicate(&mut self, name: impl Into<String>) -> String {
+        let name = name.into();
+        let name = (1..)
+            .map(|n| if n == 1 { name.clone() } else { format!("{} {}", name, n) })
+            .find(|name| !self.bindings.contains_key(name))
+            .unwrap();
+        let binding = *self.active_binding();
+        self.bindings.insert(name.clone(), binding);
+        self.active = name.clone();
+        name
+    }
+
+    pub fn set_rom(&mut self, rom: Option<&str>) {
+        self.rom = rom.map(String::from);
+    }
+
+    pub fn rom_override(&self) -> Option<&String> {
+        self.rom_overrides.get(self.rom.as_ref()?)
+    }
+
+    /// sets (or clears) the keymap override for the current ROM
+    pub fn set_rom_override(&mut self, binding: Option<String>) {
+        if let Some(rom) = &self.rom {
+            match binding {
+                Some(binding) => self.rom_overrides.insert(rom.clone(), binding),
+                None => self.rom_overrides.remove(rom),
+            };
+        }
     }
 
     pub fn available_bindings(&self) -> Vec<String> {
@@ -74,27 +222,70 @@ impl KeyBindings {
     }
 }
 
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub struct KeyCapture {
     pub enabled: bool,
     pub bindings: KeyBindings,
     pub keys: [bool; 16],
+    // non-keyboard inputs (gamepads, scripts) whose keys are combined with the keyboard's
+    pub sources: Vec<Box<dyn InputSource>>,
 }
 
 impl KeyCapture {
+    /// captures keys with the built-in keymaps: front-ends load the user's with
+    /// `KeyBindings::load_or_default`
     pub fn new() -> Self {
         Self {
             enabled: true,
             bindings: KeyBindings::new(),
             keys: [false; 16],
+            sources: Self::default_sources(),
         }
     }
 
+    #[cfg(feature = "gamepad")]
+    fn default_sources() -> Vec<Box<dyn InputSource>> {
+        match input::GamepadInput::new() {
+            Ok(gamepad) => vec![Box::new(gamepad)],
+            Err(error) => {
+                eprintln!("Gamepad input unavailable: {}", error);
+                Vec::new()
+            }
+        }
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    fn default_sources() -> Vec<Box<dyn InputSource>> {
+        Vec::new()
+    }
+
     pub fn update(&mut self, ui: &mut Ui) {
         if !self.enabled { return; }
 
         for (value, key) in KEYS.iter().zip(self.bindings.active_binding().iter()) {
             self.keys[*value as usize] = ui.input().keys_down.contains(key);
         }
+        self.poll_sources();
+    }
+
+    /// updates keypad state for front-ends that don't receive keyboard input through egui
+    pub fn update_with(&mut self, keyboard: &mut dyn InputSource) {
+        if !self.enabled { return; }
+        self.keys.fill(false);
+        keyboard.poll(&self.bindings, &mut self.keys);
+        self.poll_sources();
+    }
+
+    pub fn poll_sources(&mut self) {
+        if !self.enabled { return; }
+        for source in self.sources.iter_mut() {
+            source.poll(&self.bindings, &mut self.keys);
+        }
     }
 
     pub fn key(&self) -> Option<u8> {
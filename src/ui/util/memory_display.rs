@@ -16,16 +16,17 @@ impl MemoryDisplay {
     }
 
     pub fn ui(&mut self, ui: &mut Ui, memory: &[u8], force_on: Vec<usize>, build_label_items: impl Fn(usize) -> Vec<RichText>) -> Response {
+        // nearest neighbour sampling keeps the pixels sharp when the image is scaled
         let texture = self.texture.get_or_insert_with(|| {
             ui.ctx().load_texture(
                 "display",
                 self.image_builder.build_empty(),
-                TextureOptions::LINEAR,
+                TextureOptions::NEAREST,
             )
         });
         texture.set(
             self.image_builder.build_from_memory(memory, force_on),
-            TextureOptions::LINEAR,
+            TextureOptions::NEAREST,
         );
         let size = texture.size_vec2();
         let response = ui.image(texture, size);
@@ -1,5 +1,6 @@
 use egui::{Response, TextEdit, Ui};
 
+pub use frame_blender::{BlendMode, FrameBlender};
 pub use formatting::{Address, Byte, Decimal, Nibble, Register, Word};
 pub use memory_display::MemoryDisplay;
 pub use table::TabularData;
@@ -10,6 +11,7 @@ mod image_builder;
 mod memory_display;
 pub mod table;
 mod formatting;
+mod frame_blender;
 pub mod key_capture;
 
 /// helper function to wrap textbox add with global key capture management
@@ -0,0 +1,67 @@
+use egui::{Grid, RichText, TextStyle, Ui};
+
+use crate::command::{Command, MetaCommand};
+use crate::machine::Machine;
+use crate::ui::State;
+use crate::ui::util::Address;
+
+use super::WindowContent;
+
+/// subroutine frames reconstructed from the stack, innermost first; clicking a frame shows it in the
+/// disassembly window
+pub struct CallStack {}
+
+impl CallStack {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl WindowContent for CallStack {
+    fn name(&self) -> &'static str { "Call Stack" }
+
+    fn ui(&mut self, ui: &mut Ui, machine: &Machine, state: &mut State) {
+        let frames = machine.call_frames();
+        ui.horizontal(|ui| {
+            let finish = ui.add_enabled(frames.len() > 1, egui::Button::new("Finish"))
+                .on_hover_text("Run until the current subroutine returns");
+            if finish.clicked() {
+                state.command_buffer = Some(Command::Meta(MetaCommand::Finish));
+            }
+        });
+        let name = |address: u16| {
+            let label = state.symbols().and_then(|symbols| symbols.label_at(address));
+            match label {
+                Some(label) => format!("{} {}", Address::from(address), label),
+                None => Address::from(address).to_string(),
+            }
+        };
+        let mut focus = None;
+        ui.style_mut().override_text_style = Some(TextStyle::Monospace);
+        Grid::new("call stack").striped(true).show(ui, |ui| {
+            for heading in ["#", "Subroutine", "Location", "Returns to"] {
+                ui.label(RichText::new(heading).strong());
+            }
+            ui.end_row();
+            for (depth, frame) in frames.iter().enumerate() {
+                ui.label(depth.to_string());
+                ui.label(match (frame.entry, frame.return_address) {
+                    (Some(entry), _) => name(entry),
+                    (None, Some(_)) => String::from("?"),
+                    (None, None) => String::from("(program)"),
+                });
+                let selected = state.disassembly_focus == Some(frame.location);
+                if ui.selectable_label(selected, name(frame.location))
+                    .on_hover_text("Show in disassembly")
+                    .clicked() {
+                    focus = Some(frame.location);
+                }
+                ui.label(frame.return_address.map_or(String::new(), name));
+                ui.end_row();
+            }
+        });
+        if let Some(location) = focus {
+            state.disassembly_focus = Some(location);
+        }
+    }
+}
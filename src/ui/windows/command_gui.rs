@@ -99,11 +99,8 @@ impl CommandGui {
                 CommandWidget::new("Tick", ":tick", vec![]),
                 // todo: dropdown of allowed machine states
                 CommandWidget::new("Reset", ":reset", vec!["State"]),
-                // todo: dropdown of available ROMs
+                // see the ROM library window for available ROMs
                 CommandWidget::new("Load ROM", ":load", vec!["Filename", "Address"]),
-                CommandWidget::new("Load IBM", ":load ibm", vec![]),
-                CommandWidget::new("Load Test: BC", ":load tests/bc", vec![]),
-                CommandWidget::new("Load Test: corax89", ":load tests/corax89", vec![]),
                 CommandWidget::new("Unload ROM", ":unload", vec![]),
                 CommandWidget::new("Dump Machine", ":dump", vec!["Filename"]),
                 CommandWidget::new("Load Machine", ":load-machine", vec!["Filename"]),
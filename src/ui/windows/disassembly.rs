@@ -0,0 +1,66 @@
+use egui::{Color32, Grid, RichText, TextStyle, Ui};
+
+use crate::command::{Command, Location, MetaCommand};
+use crate::machine::{self, Machine};
+use crate::ui::State;
+use crate::ui::util::{Address, Word};
+
+use super::WindowContent;
+
+// instructions shown before and after the focused one
+const CONTEXT: u16 = 12;
+
+/// instructions around the program counter (or an address picked elsewhere, e.g. a call stack frame)
+pub struct Disassembly {}
+
+impl Disassembly {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl WindowContent for Disassembly {
+    fn name(&self) -> &'static str { "Disassembly" }
+
+    fn ui(&mut self, ui: &mut Ui, machine: &Machine, state: &mut State) {
+        let program_counter = u16::from(&machine.program_counter);
+        let focus = state.disassembly_focus.unwrap_or(program_counter);
+        ui.horizontal(|ui| {
+            ui.label(format!("Showing {}", Address::from(focus)));
+            if state.disassembly_focus.is_some() && ui.button("Follow PC").clicked() {
+                state.disassembly_focus = None;
+            }
+        });
+        let mut toggle = None;
+        ui.style_mut().override_text_style = Some(TextStyle::Monospace);
+        Grid::new("disassembly").striped(true).show(ui, |ui| {
+            let start = focus.saturating_sub(2 * CONTEXT);
+            for address in (start..=focus.saturating_add(2 * CONTEXT)).step_by(2) {
+                let Ok(machine_address) = machine::Address::try_from(address) else { break; };
+                let breakpoint = state.breakpoints.contains(&address);
+                let marker = RichText::new(if breakpoint { "●" } else { "○" })
+                    .color(if breakpoint { Color32::RED } else { Color32::DARK_GRAY });
+                if ui.small_button(marker).on_hover_text("Toggle breakpoint").clicked() {
+                    toggle = Some((address, breakpoint));
+                }
+                let mut text = |text: String| {
+                    let text = RichText::new(text);
+                    ui.label(if address == program_counter { text.strong().color(Color32::WHITE) } else { text });
+                };
+                text(format!("{}{}", if address == program_counter { "▶" } else { " " }, Address::from(address)));
+                text(machine.word_at_address(&machine_address).map_or(String::new(), |word| Word::from(word).to_string()));
+                text(machine.instruction_at_address(&machine_address).map_or(String::new(), |i| i.to_string()));
+                text(state.describe(address).unwrap_or_default());
+                ui.end_row();
+            }
+        });
+        if let Some((address, breakpoint)) = toggle {
+            let location = Location::Address(machine::Address::try_from(address).unwrap());
+            state.command_buffer = Some(Command::Meta(if breakpoint {
+                MetaCommand::Unbreak(location)
+            } else {
+                MetaCommand::Break(location)
+            }));
+        }
+    }
+}
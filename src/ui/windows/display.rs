@@ -1,13 +1,15 @@
-use egui::{RichText, Ui};
+use egui::{ComboBox, RichText, Slider, Ui};
 
 use crate::machine::{self, Machine};
+use crate::settings::{DisplayOptions, Palette};
 use crate::ui::State;
-use crate::ui::util::MemoryDisplay;
+use crate::ui::util::{BlendMode, FrameBlender, MemoryDisplay};
 
 use super::WindowContent;
 
 pub struct Display {
     display: MemoryDisplay,
+    blender: FrameBlender,
     disable_hover_info: bool,
 }
 
@@ -15,6 +17,7 @@ impl Display {
     pub fn new() -> Self {
         Self {
             display: MemoryDisplay::new(machine::config::DISPLAY_WIDTH, machine::config::DISPLAY_HEIGHT),
+            blender: FrameBlender::new(machine::config::DISPLAY_WIDTH * machine::config::DISPLAY_HEIGHT),
             disable_hover_info: false,
         }
     }
@@ -22,14 +25,28 @@ impl Display {
     pub fn minimal() -> Self {
         Self {
             display: MemoryDisplay::new(machine::config::DISPLAY_WIDTH, machine::config::DISPLAY_HEIGHT),
+            blender: FrameBlender::new(machine::config::DISPLAY_WIDTH * machine::config::DISPLAY_HEIGHT),
             disable_hover_info: true,
         }
     }
 
+    pub fn set_options(&mut self, options: &DisplayOptions) {
+        let colors = options.colors();
+        let image_builder = &mut self.display.image_builder;
+        image_builder.set_colors(colors.foreground, colors.background);
+        image_builder.pixel_size = options.zoom.clamp(1, DisplayOptions::MAX_ZOOM);
+        image_builder.scanlines = options.scanlines;
+        self.blender.mode = options.blend_mode;
+        self.blender.persistence = options.persistence;
+        self.blender.frames = options.blend_frames;
+    }
+
     // helper function to draw UI that does not require State since this widget doesn't need it and
     // it allows using this widget in the stateless basic emulator GUI
-    pub fn ui_stateless(&mut self, ui: &mut Ui, machine: &Machine) {
-        self.display.ui(ui, &machine.display, Vec::new(), |index| {
+    // `frame` counts the frames emulated so far, to blend once per frame
+    pub fn ui_stateless(&mut self, ui: &mut Ui, machine: &Machine, frame: u64) {
+        let frame = self.blender.update(frame, &machine.display);
+        self.display.ui(ui, frame, Vec::new(), |index| {
             if self.disable_hover_info { return vec![]; };
             let [x, y] = [index / machine::config::DISPLAY_WIDTH, index % machine::config::DISPLAY_WIDTH];
             let status = match machine.display.get(index) {
@@ -45,7 +62,52 @@ impl Display {
 impl WindowContent for Display {
     fn name(&self) -> &'static str { "Video Display" }
 
-    fn ui(&mut self, ui: &mut Ui, machine: &Machine, _state: &mut State) {
-        self.ui_stateless(ui, machine)
+    fn ui(&mut self, ui: &mut Ui, machine: &Machine, state: &mut State) {
+        self.set_options(&state.display_options());
+        self.ui_stateless(ui, machine, state.frames);
+        ui.collapsing("Display Options", |ui| options_ui(ui, state));
+    }
+}
+
+fn options_ui(ui: &mut Ui, state: &mut State) {
+    if let Some(rom) = &state.rom {
+        let mut rom_override = state.rom_display_override();
+        if ui.checkbox(&mut rom_override, format!("Use separate options for {}", rom.name)).changed() {
+            state.set_rom_display_override(rom_override);
+        }
     }
+    let options = state.display_options_mut();
+    ComboBox::from_label("Palette")
+        .selected_text(options.palette.name())
+        .show_ui(ui, |ui| {
+            for palette in Palette::ALL {
+                ui.selectable_value(&mut options.palette, palette, palette.name());
+            }
+        });
+    if options.palette == Palette::Custom {
+        ui.horizontal(|ui| {
+            ui.label("Foreground");
+            ui.color_edit_button_srgba(&mut options.custom_colors.foreground);
+            ui.label("Background");
+            ui.color_edit_button_srgba(&mut options.custom_colors.background);
+        });
+    }
+    ui.add(Slider::new(&mut options.zoom, 1..=DisplayOptions::MAX_ZOOM).text("Zoom"));
+    ComboBox::from_label("Frame blending")
+        .selected_text(options.blend_mode.name())
+        .show_ui(ui, |ui| {
+            for mode in BlendMode::ALL {
+                ui.selectable_value(&mut options.blend_mode, mode, mode.name());
+            }
+        });
+    match options.blend_mode {
+        BlendMode::Raw => {}
+        BlendMode::Decay => {
+            ui.add(Slider::new(&mut options.persistence, 0.0..=0.95).text("Phosphor persistence"));
+        }
+        BlendMode::Or => {
+            ui.add(Slider::new(&mut options.blend_frames, 1..=FrameBlender::MAX_FRAMES).text("Frames"));
+        }
+    };
+    ui.checkbox(&mut options.scanlines, "Scanlines");
 }
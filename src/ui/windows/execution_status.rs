@@ -1,12 +1,14 @@
-use egui::{Align, Layout, TextStyle, Ui};
+use egui::{Align, Layout, RichText, TextStyle, Ui};
 
 // this is pub so the bottom bar can also use it to create a program counter representation
 pub use program_counter_helper::ProgramCounterHelper;
 use shared::AddressTable;
 use stack_helper::StackHelper;
 
-use crate::machine::Machine;
+use crate::command::{Command, Location, MetaCommand};
+use crate::machine::{self, Machine};
 use crate::ui::State;
+use crate::ui::util::Address;
 
 use super::WindowContent;
 
@@ -33,20 +35,48 @@ impl WindowContent for ExecutionStatus {
         "Execution Status"
     }
 
-    fn ui(&mut self, ui: &mut Ui, machine: &Machine, _state: &mut State) {
+    fn ui(&mut self, ui: &mut Ui, machine: &Machine, state: &mut State) {
         ui.style_mut().override_text_style = Some(TextStyle::Monospace);
         ui.push_id(0, |ui| {
             ui.with_layout(Layout::top_down(Align::Center), |ui| {
                 ui.heading("Program Counter")
             });
-            self.program_counter.ui(ui, ProgramCounterHelper { machine });
+            self.program_counter.ui(ui, ProgramCounterHelper { machine, symbols: state.symbols() });
         });
         ui.add_space(18.0);
         ui.push_id(1, |ui| {
             ui.with_layout(Layout::top_down(Align::Center), |ui| {
                 ui.heading("Stack")
             });
-            self.stack.ui(ui, StackHelper { machine })
+            self.stack.ui(ui, StackHelper { machine, symbols: state.symbols() })
         });
+        ui.add_space(18.0);
+        ui.with_layout(Layout::top_down(Align::Center), |ui| {
+            ui.heading("Breakpoints")
+        });
+        breakpoints_ui(ui, state);
+    }
+}
+
+fn breakpoints_ui(ui: &mut Ui, state: &mut State) {
+    if state.breakpoints.is_empty() {
+        ui.label(RichText::new("none (add with `:break <address or label>`)").weak());
+    }
+    let mut remove = None;
+    for address in &state.breakpoints {
+        ui.horizontal(|ui| {
+            if ui.small_button("✖").on_hover_text("Remove breakpoint").clicked() {
+                remove = Some(*address);
+            }
+            ui.label(format!("{}", Address::from(*address)));
+            if let Some(description) = state.describe(*address) {
+                ui.label(description);
+            }
+        });
+    }
+    if let Some(address) = remove {
+        // going through the command buffer records the removal in the command history
+        let address = machine::Address::try_from(address).unwrap();
+        state.command_buffer = Some(Command::Meta(MetaCommand::Unbreak(Location::Address(address))));
     }
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 use egui::WidgetText;
 
+use crate::assembler::Symbols;
 use crate::machine::Machine;
 use crate::ui::util::TabularData;
 
@@ -7,10 +8,11 @@ use super::shared;
 
 pub struct ProgramCounterHelper<'a> {
     pub machine: &'a Machine,
+    pub symbols: Option<&'a Symbols>,
 }
 
 impl<'a> TabularData for ProgramCounterHelper<'a> {
     fn rows(&self) -> Vec<Vec<WidgetText>> {
-        vec![shared::address_row("", &self.machine.program_counter, self.machine)]
+        vec![shared::address_row("", &self.machine.program_counter, self.machine, self.symbols)]
     }
 }
\ No newline at end of file
@@ -1,10 +1,17 @@
 use egui::{Ui, WidgetText};
 
+use crate::assembler::Symbols;
 use crate::machine::{Machine, types};
 use crate::ui::util::{Address, Word};
 use crate::ui::util::table::{ColumnSpec, TableSpec, TabularData};
 
-pub fn address_row(prefix: &str, address: &types::Address, machine: &Machine) -> Vec<WidgetText> {
+pub fn address_row(prefix: &str, address: &types::Address, machine: &Machine, symbols: Option<&Symbols>) -> Vec<WidgetText> {
+    let index = address.as_index() as u16;
+    let label = symbols.and_then(|symbols| symbols.label_at(index)).unwrap_or_default();
+    let source = symbols.and_then(|symbols| {
+        let line = symbols.line_at(index)?;
+        Some(format!("{:>4} {}", line, symbols.source_at(index).unwrap_or_default()))
+    }).unwrap_or_default();
     let instruction = if let Ok(instruction) = machine.instruction_at_address(address) {
         format!("{}", instruction)
     } else {
@@ -17,6 +24,8 @@ pub fn address_row(prefix: &str, address: &types::Address, machine: &Machine) ->
             |word| Word::from(word).into()
         ).unwrap_or("".into()),
         instruction.into(),
+        label.into(),
+        source.into(),
     ]
 }
 
@@ -33,6 +42,8 @@ impl AddressTable {
                     ColumnSpec::fixed("Address", 80.0),
                     ColumnSpec::fixed("Value", 50.0),
                     ColumnSpec::fixed("Instruction", 120.0),
+                    ColumnSpec::fixed("Label", 100.0),
+                    ColumnSpec::fixed("Source", 200.0),
                 ]
             ).striped(true)
         }
@@ -1,5 +1,6 @@
 use egui::WidgetText;
 
+use crate::assembler::Symbols;
 use crate::machine::Machine;
 use crate::ui::util::TabularData;
 
@@ -7,6 +8,7 @@ use super::shared;
 
 pub struct StackHelper<'a> {
     pub machine: &'a Machine,
+    pub symbols: Option<&'a Symbols>,
 }
 
 impl<'a> TabularData for StackHelper<'a> {
@@ -18,9 +20,9 @@ impl<'a> TabularData for StackHelper<'a> {
                 format!(" {:01X} ", index)
             };
             if let Some(address) = address {
-                shared::address_row(&prefix, address, self.machine)
+                shared::address_row(&prefix, address, self.machine, self.symbols)
             } else {
-                vec![prefix.into(), "".into(), "".into(), "".into()]
+                vec![prefix.into(), "".into(), "".into(), "".into(), "".into(), "".into()]
             }
         }).collect();
         rows.push(vec![
@@ -28,6 +30,8 @@ impl<'a> TabularData for StackHelper<'a> {
             "".into(),
             "".into(),
             "".into(),
+            "".into(),
+            "".into(),
         ]);
         rows
     }
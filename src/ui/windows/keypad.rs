@@ -1,5 +1,6 @@
-use egui::{Button, Color32, ComboBox, Response, RichText, Ui};
+use egui::{Button, Color32, ComboBox, Event, Grid, Key, Response, RichText, Ui};
 
+use crate::input;
 use crate::machine::Machine;
 use crate::ui::State;
 use crate::ui::util::key_capture::KEYS;
@@ -9,12 +10,36 @@ use super::WindowContent;
 
 pub struct Keypad {
     show_binds: bool,
+    rebind_mode: bool,
+    // index (into KEYS) of the keypad key waiting for a host key press
+    rebinding: Option<usize>,
 }
 
 impl Keypad {
     pub fn new() -> Self {
         Self {
             show_binds: false,
+            rebind_mode: false,
+            rebinding: None,
+        }
+    }
+
+    fn capture_rebind(&mut self, ui: &mut Ui, state: &mut State) {
+        let Some(index) = self.rebinding else { return; };
+        // don't let the key we are about to bind also press a keypad key
+        state.key_capture_suspended = true;
+        let pressed = ui.input().events.iter().find_map(|event| match event {
+            Event::Key { key, pressed: true, .. } => Some(*key),
+            _ => None,
+        });
+        if let Some(key) = pressed {
+            // <ESC> cancels rebinding
+            if key != Key::Escape {
+                state.key_capture.bindings.rebind(index, key);
+                save_bindings(state);
+            }
+            self.rebinding = None;
+            state.key_capture_suspended = false;
         }
     }
 }
@@ -25,34 +50,124 @@ impl WindowContent for Keypad {
     }
 
     fn ui(&mut self, ui: &mut Ui, _machine: &Machine, state: &mut State) {
+        self.capture_rebind(ui, state);
         for row in 0..4 {
             ui.horizontal(|ui| {
                 for col in 0..4 {
                     let index = row * 4 + col;
                     let value = KEYS[index];
-                    let label = if self.show_binds {
+                    let label = if self.rebinding == Some(index) {
+                        format!("{} <?>", Nibble::from(value))
+                    } else if self.show_binds || self.rebind_mode {
                         let key = state.key_capture.bindings.active_binding()[index];
                         let bind = format!("{:?}", key).chars().last().unwrap();
                         format!("{} <{}>", Nibble::from(value), bind)
                     } else {
                         format!(" {} ", Nibble::from(value))
                     };
-                    key_ui(ui, label, &mut state.key_capture.keys[value as usize]);
+                    if self.rebind_mode {
+                        if key_ui(ui, label, &mut false).clicked() {
+                            self.rebinding = Some(index);
+                        }
+                    } else {
+                        key_ui(ui, label, &mut state.key_capture.keys[value as usize]);
+                    }
                 }
             });
         }
         ui.separator();
         ui.checkbox(&mut state.key_capture.enabled, "Capture key presses");
         ui.checkbox(&mut self.show_binds, "Show key bindings");
+        if ui.checkbox(&mut self.rebind_mode, "Rebind keys (click a key, then press a host key)").changed()
+            && !self.rebind_mode && self.rebinding.take().is_some() {
+            state.key_capture_suspended = false;
+        }
         ui.separator();
-        ui.label("Select Keymap");
-        ComboBox::from_label("")
-            .selected_text(format!("{}", state.key_capture.bindings.active))
+        keymap_ui(ui, state);
+        ui.collapsing("Gamepad Buttons", |ui| button_map_ui(ui, state));
+    }
+}
+
+fn keymap_ui(ui: &mut Ui, state: &mut State) {
+    let bindings = &mut state.key_capture.bindings;
+    let mut changed = false;
+    ui.label("Select Keymap");
+    let mut active = bindings.active.clone();
+    ComboBox::from_id_source("keymap")
+        .selected_text(active.to_string())
+        .show_ui(ui, |ui| {
+            for binding in bindings.available_bindings() {
+                ui.selectable_value(&mut active, binding.clone(), binding);
+            }
+        });
+    if active != bindings.active {
+        bindings.active = active;
+        changed = true;
+    }
+    if let Some(rom) = &state.rom {
+        ui.label(format!("Keymap for {}", rom.name));
+        let mut rom_override = bindings.rom_override().cloned();
+        ComboBox::from_id_source("rom keymap")
+            .selected_text(rom_override.clone().unwrap_or(String::from("(selected keymap)")))
             .show_ui(ui, |ui| {
-                for binding in state.key_capture.bindings.available_bindings() {
-                    ui.selectable_value(&mut state.key_capture.bindings.active, binding.clone(), binding);
+                ui.selectable_value(&mut rom_override, None, "(selected keymap)");
+                for binding in bindings.available_bindings() {
+                    ui.selectable_value(&mut rom_override, Some(binding.clone()), binding);
                 }
             });
+        if rom_override.as_ref() != bindings.rom_override() {
+            bindings.set_rom_override(rom_override);
+            changed = true;
+        }
+    }
+    if ui.button("Duplicate keymap").on_hover_text("Copy the keymap in use to a new keymap").clicked() {
+        let name = format!("{} (copy)", bindings.effective());
+        bindings.duplicate(name);
+        changed = true;
+    }
+    if changed {
+        save_bindings(state);
+    }
+}
+
+fn button_map_ui(ui: &mut Ui, state: &mut State) {
+    let bindings = &mut state.key_capture.bindings;
+    let mut changed = false;
+    if let Some(rom) = &state.rom {
+        let mut rom_map = bindings.has_rom_button_map();
+        if ui.checkbox(&mut rom_map, format!("Use separate buttons for {}", rom.name)).changed() {
+            bindings.set_rom_button_map(rom_map);
+            changed = true;
+        }
+    }
+    Grid::new("button map").show(ui, |ui| {
+        for button in input::Button::ALL {
+            ui.label(format!("{:?}", button));
+            let mut key = bindings.button_map().get(&button).copied();
+            let selected = key.map_or(String::from("(none)"), |key| Nibble::from(key).to_string());
+            ComboBox::from_id_source(format!("button {:?}", button))
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut key, None, "(none)");
+                    for value in 0..16u8 {
+                        ui.selectable_value(&mut key, Some(value), Nibble::from(value).to_string());
+                    }
+                });
+            if key.as_ref() != bindings.button_map().get(&button) {
+                bindings.bind_button(button, key);
+                changed = true;
+            }
+            ui.end_row();
+        }
+    });
+    if changed {
+        save_bindings(state);
+    }
+}
+
+fn save_bindings(state: &mut State) {
+    if let Err(error) = state.key_capture.bindings.save() {
+        state.error = Some(error);
     }
 }
 
@@ -64,4 +179,4 @@ fn key_ui(ui: &mut Ui, label: String, active: &mut bool) -> Response {
     );
     *active |= response.is_pointer_button_down_on();
     response
-}
\ No newline at end of file
+}
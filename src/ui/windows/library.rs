@@ -0,0 +1,128 @@
+use egui::{Color32, Grid, RichText, TextEdit, Ui};
+
+use crate::command::{Command, MetaCommand};
+use crate::library::{LibraryEntry, RomLibrary};
+use crate::machine::Machine;
+use crate::ui::State;
+use crate::ui::util::{self, Nibble};
+
+use super::WindowContent;
+
+pub struct Library {
+    library: RomLibrary,
+    scanned: bool,
+    new_directory: String,
+}
+
+impl Library {
+    pub fn new() -> Self {
+        Self {
+            library: RomLibrary::new(),
+            scanned: false,
+            new_directory: String::new(),
+        }
+    }
+
+    fn scan(&mut self, state: &mut State) {
+        self.library.scan(&state.rom_directories);
+        self.scanned = true;
+    }
+
+    fn directories_ui(&mut self, ui: &mut Ui, state: &mut State) {
+        let mut changed = false;
+        let mut remove = None;
+        for (index, directory) in state.rom_directories.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("✖").on_hover_text("Remove directory").clicked() {
+                    remove = Some(index);
+                }
+                ui.label(directory);
+            });
+        }
+        if let Some(index) = remove {
+            state.rom_directories.remove(index);
+            changed = true;
+        }
+        ui.horizontal(|ui| {
+            util::add_text_edit(ui, state, TextEdit::singleline(&mut self.new_directory)
+                .hint_text("Directory")
+                .desired_width(160.0));
+            if ui.button("Add").clicked() && !self.new_directory.is_empty() {
+                state.rom_directories.push(self.new_directory.drain(..).collect());
+                changed = true;
+            }
+        });
+        if changed {
+            self.scan(state);
+        }
+    }
+}
+
+impl WindowContent for Library {
+    fn name(&self) -> &'static str { "ROM Library" }
+
+    fn ui(&mut self, ui: &mut Ui, _machine: &Machine, state: &mut State) {
+        if !self.scanned {
+            self.scan(state);
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Rescan").clicked() {
+                self.scan(state);
+            }
+            ui.label(format!("{} ROMs found", self.library.entries.len()));
+            if !self.library.warnings.is_empty() {
+                ui.label(RichText::new(format!("{} skipped", self.library.warnings.len())).color(Color32::DEBUG_COLOR))
+                    .on_hover_text(self.library.warnings.join("\n"));
+            }
+        });
+        ui.collapsing("Directories", |ui| self.directories_ui(ui, state));
+        ui.separator();
+        Grid::new("rom library").striped(true).show(ui, |ui| {
+            ui.label(RichText::new("Title").strong());
+            ui.label(RichText::new("Author").strong());
+            ui.label(RichText::new("Platform").strong());
+            ui.label(RichText::new("File").strong());
+            ui.end_row();
+            for entry in &self.library.entries {
+                entry_ui(ui, entry, state);
+                ui.end_row();
+            }
+        });
+    }
+}
+
+fn entry_ui(ui: &mut Ui, entry: &LibraryEntry, state: &mut State) {
+    ui.label(entry.title()).on_hover_ui(|ui| hover_ui(ui, entry));
+    match &entry.info {
+        Some(info) => {
+            ui.label(info.author.clone().unwrap_or_default());
+            let platform = RichText::new(info.platform.to_string());
+            ui.label(if info.platform.is_supported() { platform } else { platform.color(Color32::RED) });
+        }
+        None => {
+            ui.label("");
+            ui.label(RichText::new("unknown").weak());
+        }
+    };
+    ui.label(entry.path.display().to_string());
+    if ui.button("Load").clicked() {
+        let path = entry.path.to_string_lossy().into_owned();
+        state.command_buffer = Some(Command::Meta(MetaCommand::LoadRom(path, None)));
+    }
+}
+
+fn hover_ui(ui: &mut Ui, entry: &LibraryEntry) {
+    if let Some(info) = &entry.info {
+        if let Some(description) = &info.description {
+            ui.label(description);
+        }
+        if !info.platform.is_supported() {
+            ui.colored_label(Color32::RED, format!("{} ROMs are not supported", info.platform));
+        }
+        for (action, keys) in &info.keys {
+            let keys: Vec<_> = keys.iter().map(|key| Nibble::from(*key).to_string()).collect();
+            ui.label(format!(" · {}: {}", action, keys.join(" ")));
+        }
+    }
+    ui.label(RichText::new(format!("SHA-1 {}", entry.sha1)).monospace().weak());
+}
@@ -1,4 +1,5 @@
-use egui::{Color32, RichText, Ui};
+use egui::{Color32, ComboBox, RichText, Ui};
+use egui::ecolor::Hsva;
 
 use crate::machine::{Machine, types};
 use crate::ui::State;
@@ -9,11 +10,43 @@ use super::WindowContent;
 
 pub struct Memory {
     display: MemoryDisplay,
+    overlay: Overlay,
 }
 
 impl Memory {
     pub fn new() -> Self {
-        Self { display: MemoryDisplay::new(64, 64) }
+        Self { display: MemoryDisplay::new(64, 64), overlay: Overlay::Tags }
+    }
+
+    // colours each byte by how often it was accessed, on a log scale from blue (rarely) to red (often)
+    fn heatmap(&mut self, counts: &[u64]) -> Vec<u8> {
+        let max = counts.iter().copied().max().unwrap_or_default().max(1) as f32;
+        counts.iter().enumerate().map(|(index, count)| {
+            let heat = (*count as f32).ln_1p() / max.ln_1p();
+            self.display.image_builder.color_map[index] = Hsva::new(0.66 * (1.0 - heat), 1.0, 1.0, 1.0).into();
+            if *count > 0 { 255 } else { 0 }
+        }).collect()
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Overlay {
+    Tags,
+    Executions,
+    Reads,
+    Writes,
+}
+
+impl Overlay {
+    const ALL: [Overlay; 4] = [Self::Tags, Self::Executions, Self::Reads, Self::Writes];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Tags => "Memory tags",
+            Self::Executions => "Execution heatmap",
+            Self::Reads => "Read heatmap",
+            Self::Writes => "Write heatmap",
+        }
     }
 }
 
@@ -21,12 +54,32 @@ impl WindowContent for Memory {
     fn name(&self) -> &'static str { "Memory" }
 
     fn ui(&mut self, ui: &mut Ui, machine: &Machine, state: &mut State) {
-        self.display.image_builder.color_map.fill(Color32::WHITE);
-        for (tag, range) in state.memory_tags.iter() {
-            self.display.image_builder.color_map[range.clone()].fill(tag.color());
+        ui.horizontal(|ui| {
+            ComboBox::from_id_source("memory overlay")
+                .selected_text(self.overlay.name())
+                .show_ui(ui, |ui| {
+                    for overlay in Overlay::ALL {
+                        ui.selectable_value(&mut self.overlay, overlay, overlay.name());
+                    }
+                });
+            if self.overlay != Overlay::Tags && machine.profiler.is_none() {
+                ui.label(RichText::new("enable profiling with `:profile on`").weak());
+            }
+        });
+        let heat = match (&machine.profiler, self.overlay) {
+            (Some(profiler), Overlay::Executions) => Some(self.heatmap(&profiler.executions)),
+            (Some(profiler), Overlay::Reads) => Some(self.heatmap(&profiler.reads)),
+            (Some(profiler), Overlay::Writes) => Some(self.heatmap(&profiler.writes)),
+            _ => None,
+        };
+        if heat.is_none() {
+            self.display.image_builder.color_map.fill(Color32::WHITE);
+            for (tag, range) in state.memory_tags.iter() {
+                self.display.image_builder.color_map[range.clone()].fill(state.tag_color(tag));
+            }
         }
         self.display.ui(ui,
-                        &machine.memory,
+                        heat.as_deref().unwrap_or(&machine.memory),
                         vec![usize::from(&machine.program_counter),
                              usize::from(&machine.program_counter) + 1,
                              usize::from(&machine.index)],
@@ -48,9 +101,16 @@ fn hover_text(index: usize, machine: &Machine, state: &State) -> Vec<RichText> {
     if let Ok(instruction) = machine.instruction_at_address(&address) {
         lines.push(RichText::new(format!(" · Instruction: {}", instruction)));
     }
+    if let Some(description) = state.describe(index as u16) {
+        lines.push(RichText::new(format!(" · Source: {}", description)));
+    }
+    if let Some(profiler) = &machine.profiler {
+        lines.push(RichText::new(format!(" · Executed {}×, read {}×, written {}×",
+                                         profiler.executions[index], profiler.reads[index], profiler.writes[index])));
+    }
     for (tag, range) in &state.memory_tags {
         if range.contains(&index) {
-            lines.push(RichText::from(format!(" · {}", tag.name())).color(tag.color()))
+            lines.push(RichText::from(format!(" · {}", tag.name())).color(state.tag_color(tag)))
         }
     };
     lines
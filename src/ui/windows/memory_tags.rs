@@ -0,0 +1,78 @@
+use egui::{Grid, RichText, TextEdit, Ui};
+
+use crate::command::{Command, MetaCommand};
+use crate::machine::Machine;
+use crate::ui::{MemoryTag, State};
+use crate::ui::util::{self, Address};
+
+use super::WindowContent;
+
+/// legend for the colours used by the memory window, with editing of user-defined tags
+pub struct MemoryTags {
+    start: String,
+    end: String,
+    name: String,
+}
+
+impl MemoryTags {
+    pub fn new() -> Self {
+        Self {
+            start: String::new(),
+            end: String::new(),
+            name: String::new(),
+        }
+    }
+
+    fn add_tag_ui(&mut self, ui: &mut Ui, state: &mut State) {
+        ui.horizontal(|ui| {
+            for (text, hint) in [(&mut self.start, "Start"), (&mut self.end, "End")] {
+                util::add_text_edit(ui, state, TextEdit::singleline(text).hint_text(hint).desired_width(50.0));
+            }
+            util::add_text_edit(ui, state, TextEdit::singleline(&mut self.name).hint_text("Name").desired_width(120.0));
+            if ui.button("Tag").on_hover_text("Tag memory from start up to (not including) end").clicked() {
+                // parsed like the equivalent command, so errors show up in the same place
+                state.parse_command(&format!(":tag {} {} {}", self.start, self.end, self.name));
+                if state.command_buffer.is_some() {
+                    self.name.clear();
+                }
+            }
+        });
+    }
+}
+
+impl WindowContent for MemoryTags {
+    fn name(&self) -> &'static str { "Memory Tags" }
+
+    fn ui(&mut self, ui: &mut Ui, _machine: &Machine, state: &mut State) {
+        let tags: Vec<_> = state.memory_tags.iter()
+            .map(|(tag, range)| (tag.name(), tag.color(), range.clone(), matches!(tag, MemoryTag::User { .. })))
+            .collect();
+        Grid::new("memory tags").striped(true).show(ui, |ui| {
+            ui.label(RichText::new("Colour").strong());
+            ui.label(RichText::new("Tag").strong());
+            ui.label(RichText::new("Start").strong());
+            ui.label(RichText::new("End").strong());
+            ui.end_row();
+            for (name, default, range, user) in tags {
+                let mut color = state.tag_colors.get(&name).copied().unwrap_or(default);
+                if ui.color_edit_button_srgba(&mut color).changed() {
+                    state.tag_colors.insert(name.clone(), color);
+                }
+                ui.label(&name);
+                ui.monospace(Address::from(range.start).to_string());
+                ui.monospace(Address::from(range.end).to_string());
+                ui.horizontal(|ui| {
+                    if state.tag_colors.contains_key(&name) && ui.small_button("↺").on_hover_text("Default colour").clicked() {
+                        state.tag_colors.remove(&name);
+                    }
+                    if user && ui.small_button("✖").on_hover_text("Remove tag").clicked() {
+                        state.command_buffer = Some(Command::Meta(MetaCommand::Untag(name.clone())));
+                    }
+                });
+                ui.end_row();
+            }
+        });
+        ui.separator();
+        self.add_tag_ui(ui, state);
+    }
+}
@@ -1,15 +1,21 @@
 use egui::{Context, Response, Ui};
 
+use call_stack::CallStack;
 use command_gui::CommandGui;
 use command_history::CommandHistory;
+use disassembly::Disassembly;
 pub use display::Display;
 use execution_status::ExecutionStatus;
 pub use execution_status::ProgramCounterHelper;
 use index::Index;
 use keypad::Keypad;
+use library::Library;
 pub use memory::Memory;
+use memory_tags::MemoryTags;
+use profiler::Profiler;
 use registers::Registers;
 use timers::Timers;
+use trace::Trace;
 
 use crate::machine::Machine;
 
@@ -21,9 +27,15 @@ mod execution_status;
 mod registers;
 mod index;
 mod memory;
+mod memory_tags;
 mod display;
 mod command_gui;
 mod keypad;
+mod library;
+mod profiler;
+mod trace;
+mod call_stack;
+mod disassembly;
 
 pub trait WindowContent {
     fn name(&self) -> &'static str;
@@ -67,10 +79,16 @@ pub fn create_all() -> Vec<Window> {
         Window::new(Box::new(CommandGui::instruction())),
         Window::new(Box::new(Display::new())),
         Window::new(Box::new(Memory::new())),
+        Window::new(Box::new(MemoryTags::new())),
         Window::new(Box::new(Index::new())),
         Window::new(Box::new(Timers::new())),
         Window::new(Box::new(Registers::new())),
         Window::new(Box::new(ExecutionStatus::new())),
+        Window::new(Box::new(CallStack::new())),
+        Window::new(Box::new(Disassembly::new())),
         Window::new(Box::new(Keypad::new())),
+        Window::new(Box::new(Library::new())),
+        Window::new(Box::new(Profiler::new())),
+        Window::new(Box::new(Trace::new())),
     ]
 }
\ No newline at end of file
@@ -0,0 +1,99 @@
+use egui::{Grid, RichText, TextEdit, Ui};
+
+use crate::command::{Command, MetaCommand, Profiling};
+use crate::machine::{self, Machine, Profiler as MachineProfiler};
+use crate::ui::State;
+use crate::ui::util::{self, Address};
+
+use super::WindowContent;
+
+const HOT_SPOTS: usize = 20;
+
+/// hot spots of the profile collected by the machine, by instruction and by subroutine
+pub struct Profiler {
+    coverage_path: String,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self {
+            coverage_path: String::from("coverage.json"),
+        }
+    }
+
+    fn controls_ui(&mut self, ui: &mut Ui, machine: &Machine, state: &mut State) {
+        let command = |profiling| Some(Command::Meta(MetaCommand::Profile(profiling)));
+        ui.horizontal(|ui| {
+            if machine.profiler.is_some() {
+                if ui.button("Stop").clicked() {
+                    state.command_buffer = command(Profiling::Off);
+                }
+                if ui.button("Reset").clicked() {
+                    state.command_buffer = command(Profiling::Reset);
+                }
+            } else if ui.button("Start").clicked() {
+                state.command_buffer = command(Profiling::On);
+            }
+            ui.separator();
+            util::add_text_edit(ui, state, TextEdit::singleline(&mut self.coverage_path).desired_width(140.0));
+            if ui.button("Export coverage").clicked() {
+                state.command_buffer = Some(Command::Meta(MetaCommand::Coverage(self.coverage_path.clone())));
+            }
+        });
+    }
+}
+
+impl WindowContent for Profiler {
+    fn name(&self) -> &'static str { "Profiler" }
+
+    fn ui(&mut self, ui: &mut Ui, machine: &Machine, state: &mut State) {
+        self.controls_ui(ui, machine, state);
+        ui.separator();
+        let Some(profiler) = &machine.profiler else {
+            ui.label(RichText::new("not profiling").weak());
+            return;
+        };
+        let total = profiler.total_executions().max(1) as f64;
+        ui.label(format!("{} instructions executed", profiler.total_executions()));
+        ui.collapsing("Hot instructions", |ui| hot_spots_ui(ui, machine, profiler, state, total));
+        ui.collapsing("Subroutines", |ui| subroutines_ui(ui, profiler, state, total));
+    }
+}
+
+fn hot_spots_ui(ui: &mut Ui, machine: &Machine, profiler: &MachineProfiler, state: &State, total: f64) {
+    Grid::new("profiler hot spots").striped(true).show(ui, |ui| {
+        for heading in ["Address", "Label", "Count", "%", "Instruction"] {
+            ui.label(RichText::new(heading).strong());
+        }
+        ui.end_row();
+        for (address, count) in profiler.hot_spots().into_iter().take(HOT_SPOTS) {
+            ui.monospace(Address::from(address).to_string());
+            ui.label(state.symbols().and_then(|symbols| symbols.label_at(address)).unwrap_or_default());
+            ui.monospace(count.to_string());
+            ui.monospace(format!("{:.1}", 100.0 * count as f64 / total));
+            let instruction = machine::Address::try_from(address).ok()
+                .and_then(|address| machine.instruction_at_address(&address).ok());
+            ui.monospace(instruction.map_or(String::new(), |instruction| instruction.to_string()));
+            ui.end_row();
+        }
+    });
+}
+
+fn subroutines_ui(ui: &mut Ui, profiler: &MachineProfiler, state: &State, total: f64) {
+    let mut subroutines: Vec<_> = profiler.subroutines.iter().collect();
+    subroutines.sort_by_key(|(_, subroutine)| std::cmp::Reverse(subroutine.instructions));
+    Grid::new("profiler subroutines").striped(true).show(ui, |ui| {
+        for heading in ["Entry", "Label", "Calls", "Instructions", "%"] {
+            ui.label(RichText::new(heading).strong());
+        }
+        ui.end_row();
+        for (address, subroutine) in subroutines {
+            ui.monospace(Address::from(*address).to_string());
+            ui.label(state.symbols().and_then(|symbols| symbols.label_at(*address)).unwrap_or_default());
+            ui.monospace(subroutine.calls.to_string());
+            ui.monospace(subroutine.instructions.to_string());
+            ui.monospace(format!("{:.1}", 100.0 * subroutine.instructions as f64 / total));
+            ui.end_row();
+        }
+    });
+}
@@ -0,0 +1,94 @@
+use egui::{RichText, ScrollArea, TextEdit, TextStyle, Ui};
+use ringbuffer::RingBufferExt;
+
+use crate::command::{Command, MetaCommand, Tracing};
+use crate::machine::Machine;
+use crate::trace::{InstructionKind, TraceFilter};
+use crate::ui::State;
+use crate::ui::util::{self, Address};
+
+use super::WindowContent;
+
+/// the most recent instructions recorded by the tracer, with controls for its filter
+pub struct Trace {
+    new_range: String,
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Self { new_range: String::new() }
+    }
+
+    fn filter_ui(&mut self, ui: &mut Ui, state: &mut State) {
+        let mut new_range = None;
+        let Some(tracer) = &mut state.tracer else { return; };
+        let filter = &mut tracer.filter;
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Kinds:");
+            for kind in InstructionKind::ALL {
+                let mut selected = filter.kinds.contains(&kind);
+                if ui.checkbox(&mut selected, kind.name()).changed() {
+                    if selected { filter.kinds.insert(kind); } else { filter.kinds.remove(&kind); }
+                }
+            }
+        });
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Ranges:");
+            filter.ranges.retain(|range| {
+                let label = format!("{}..{} ✖", Address::from(range.start), Address::from(range.end));
+                !ui.small_button(label).on_hover_text("Remove range").clicked()
+            });
+            if ui.button("Add").clicked() {
+                new_range = Some(self.new_range.clone());
+            }
+        });
+        util::add_text_edit(ui, state, TextEdit::singleline(&mut self.new_range)
+            .hint_text("0x200..0x300")
+            .desired_width(120.0));
+        if let Some(text) = new_range {
+            match TraceFilter::parse_range(&text) {
+                Ok(range) => {
+                    state.tracer.as_mut().unwrap().filter.ranges.push(range);
+                    self.new_range.clear();
+                }
+                Err(error) => state.error = Some(error),
+            }
+        }
+    }
+}
+
+impl WindowContent for Trace {
+    fn name(&self) -> &'static str { "Trace" }
+
+    fn ui(&mut self, ui: &mut Ui, _machine: &Machine, state: &mut State) {
+        let command = |tracing| Some(Command::Meta(MetaCommand::Trace(tracing)));
+        ui.horizontal(|ui| {
+            if state.tracer.is_some() {
+                if ui.button("Stop").clicked() {
+                    state.command_buffer = command(Tracing::Off);
+                }
+                if ui.button("Clear").clicked() {
+                    state.command_buffer = command(Tracing::Clear);
+                }
+            } else if ui.button("Start").clicked() {
+                state.command_buffer = command(Tracing::On);
+            }
+        });
+        if state.tracer.is_none() {
+            ui.label(RichText::new("not tracing").weak());
+            return;
+        }
+        self.filter_ui(ui, state);
+        ui.separator();
+        let Some(buffer) = state.tracer.as_ref().and_then(|tracer| tracer.buffer.as_ref()) else { return; };
+        let symbols = state.symbols();
+        ui.style_mut().override_text_style = Some(TextStyle::Monospace);
+        ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
+            for entry in buffer.iter() {
+                let label = symbols.and_then(|symbols| symbols.label_at(entry.address))
+                    .map_or(String::new(), |label| format!("{:<12} ", label));
+                ui.label(format!("{}{}", label, entry));
+            }
+        });
+    }
+}
@@ -0,0 +1,27 @@
+//! Entry points for the WebAssembly build, called from JavaScript once the module is loaded, e.g.
+//! `await start_repl("canvas")`. Files the REPL loads and saves live in the browser's local storage.
+
+use eframe::WebOptions;
+use wasm_bindgen::prelude::*;
+
+use crate::app::{EmulatorApp, ReplApp};
+use crate::emulator::{Emulator, EmulatorConfig};
+use crate::settings::Settings;
+
+/// runs the REPL in the canvas with id `canvas_id`
+#[wasm_bindgen]
+pub async fn start_repl(canvas_id: String) -> Result<(), JsValue> {
+    eframe::start_web(&canvas_id, WebOptions::default(), Box::new(|cc| Box::new(ReplApp::new(cc)))).await?;
+    Ok(())
+}
+
+/// runs a binary CHIP-8 ROM in the canvas with id `canvas_id`
+#[wasm_bindgen]
+pub async fn start_emulator(canvas_id: String, name: String, rom: Vec<u8>) -> Result<(), JsValue> {
+    let emulator = Emulator::from_bytes(name, rom, EmulatorConfig::new());
+    let display_options = Settings::load_or_default().display_for(&emulator.rom_name);
+    eframe::start_web(&canvas_id, WebOptions::default(), Box::new(move |cc| {
+        Box::new(EmulatorApp::new(cc, emulator, &display_options))
+    })).await?;
+    Ok(())
+}
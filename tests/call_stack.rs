@@ -0,0 +1,119 @@
+use chipper8::repl::Repl;
+
+mod common;
+
+const SOURCE: &str = ": main
+  outer
+  loop again
+: outer
+  inner
+  v1 := 1
+  return
+: inner
+  v0 := 1
+  v0 += 1
+  return
+";
+
+fn execute(repl: &mut Repl, command: &str) {
+    repl.state.parse_command(command);
+    repl.execute_buffered();
+}
+
+fn run(repl: &mut Repl) {
+    while repl.state.running {
+        repl.step_running();
+    }
+}
+
+fn repl() -> Repl {
+    common::load_source("nested.8o", SOURCE)
+}
+
+#[test]
+fn test_call_frames() {
+    let mut repl = repl();
+    execute(&mut repl, ":break inner");
+    execute(&mut repl, ":play");
+    run(&mut repl);
+    let outer = repl.state.symbols().unwrap().address_of("outer").unwrap();
+    let inner = repl.state.symbols().unwrap().address_of("inner").unwrap();
+    let frames = repl.machine.call_frames();
+    assert_eq!(frames.len(), 3);
+    assert_eq!((frames[0].entry, frames[0].location, frames[0].return_address), (Some(inner), inner, Some(outer + 2)));
+    assert_eq!((frames[1].entry, frames[1].location, frames[1].return_address), (Some(outer), outer, Some(0x202)));
+    assert_eq!((frames[2].entry, frames[2].location, frames[2].return_address), (None, 0x200, None));
+}
+
+#[test]
+fn test_finish() {
+    let mut repl = repl();
+    execute(&mut repl, ":finish");
+    assert!(repl.state.error.is_some(), "not in a subroutine yet");
+    execute(&mut repl, ":break inner");
+    execute(&mut repl, ":play");
+    run(&mut repl);
+    execute(&mut repl, ":finish");
+    run(&mut repl);
+    // back in `outer`, just after the call
+    assert_eq!(repl.machine.stack.pointer, 1);
+    assert_eq!(repl.machine.registers[0], 2);
+    assert_eq!(repl.machine.registers[1], 0);
+    let outer = repl.state.symbols().unwrap().address_of("outer").unwrap();
+    assert_eq!(u16::from(&repl.machine.program_counter), outer + 2);
+}
+
+#[test]
+fn test_next_steps_over_calls() {
+    let mut repl = repl();
+    execute(&mut repl, ":next");
+    run(&mut repl);
+    assert_eq!(u16::from(&repl.machine.program_counter), 0x202);
+    assert_eq!(repl.machine.stack.pointer, 0);
+    assert_eq!((repl.machine.registers[0], repl.machine.registers[1]), (2, 1));
+    // not a call: a single step
+    execute(&mut repl, ":next");
+    assert!(!repl.state.running);
+    assert_eq!(u16::from(&repl.machine.program_counter), 0x202);
+}
+
+#[test]
+fn test_until_and_run() {
+    let mut repl = repl();
+    execute(&mut repl, ":until inner");
+    run(&mut repl);
+    let inner = repl.state.symbols().unwrap().address_of("inner").unwrap();
+    assert_eq!(u16::from(&repl.machine.program_counter), inner);
+    assert_eq!(repl.machine.stack.pointer, 2);
+    execute(&mut repl, ":run 2");
+    run(&mut repl);
+    assert_eq!(u16::from(&repl.machine.program_counter), inner + 4);
+    assert_eq!(repl.machine.registers[0], 2);
+}
+
+#[test]
+fn test_run_zero() {
+    let mut repl = repl();
+    execute(&mut repl, ":run 0");
+    assert!(repl.state.error.is_some());
+    assert!(repl.state.stop_condition.is_none());
+    execute(&mut repl, ":play");
+    for _ in 0..10 {
+        repl.step_running();
+    }
+    assert!(repl.state.running);
+}
+
+#[test]
+fn test_pausing_forgets_stop_condition() {
+    let mut repl = repl();
+    execute(&mut repl, ":until inner");
+    execute(&mut repl, ":pause");
+    repl.run_frames();
+    assert!(repl.state.stop_condition.is_none());
+    execute(&mut repl, ":play");
+    for _ in 0..10 {
+        repl.step_running();
+    }
+    assert!(repl.state.running, "the old :until no longer stops the machine");
+}
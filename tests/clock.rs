@@ -0,0 +1,32 @@
+use chipper8::clock::{FRAME_TIME, FrameClock, Instant};
+
+#[test]
+fn test_frames_due() {
+    let start = Instant::now();
+    let mut clock = FrameClock::new();
+    // the first frame is due straight away
+    assert_eq!(clock.frames_due(start), 1);
+    assert_eq!(clock.frames_due(start + FRAME_TIME / 2), 0);
+    assert_eq!(clock.until_next_frame(start + FRAME_TIME / 2), FRAME_TIME - FRAME_TIME / 2);
+    // late calls don't push the schedule back
+    assert_eq!(clock.frames_due(start + FRAME_TIME * 3 / 2), 1);
+    assert_eq!(clock.until_next_frame(start + FRAME_TIME * 3 / 2), FRAME_TIME / 2);
+    assert_eq!(clock.frames_due(start + FRAME_TIME * 4), 3);
+    // after falling far behind, only a few frames are caught up on before starting over
+    let later = start + FRAME_TIME * 100;
+    assert_eq!(clock.frames_due(later), 5);
+    assert_eq!(clock.until_next_frame(later), FRAME_TIME);
+    clock.reset();
+    assert_eq!(clock.frames_due(later), 1);
+}
+
+#[test]
+fn test_instructions_per_frame() {
+    let mut clock = FrameClock::new();
+    assert_eq!(clock.instructions_per_frame(600.0), 10);
+    // fractions carry over, so no instructions are lost at any speed
+    let instructions: usize = (0..60).map(|_| clock.instructions_per_frame(90.0)).sum();
+    assert_eq!(instructions, 90);
+    let instructions: Vec<_> = (0..4).map(|_| clock.instructions_per_frame(30.0)).collect();
+    assert_eq!(instructions, [0, 1, 0, 1]);
+}
@@ -0,0 +1,36 @@
+// helpers shared by the integration tests; each test crate uses only some of them
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use chipper8::repl::Repl;
+
+/// a new, empty temporary directory: tests run in parallel, so each gets its own files
+pub fn temp_dir(name: &str) -> PathBuf {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNT.fetch_add(1, Ordering::Relaxed);
+    let directory = std::env::temp_dir().join(format!("chipper8_{}_{}_{}", name, process::id(), count));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// writes `contents` to a file called `name` in a new temporary directory
+pub fn temp_file(name: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+    let path = temp_dir("test").join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+/// a REPL with Octo `source`, written to a file called `name`, loaded with `:load`
+pub fn load_source(name: &str, source: &str) -> Repl {
+    let path = temp_file(name, source);
+    let mut repl = Repl::new();
+    repl.state.parse_command(&format!(":load {}", path.display()));
+    repl.execute_buffered();
+    assert!(repl.state.error.is_none(), "loading {}: {:?}", name, repl.state.error);
+    repl
+}
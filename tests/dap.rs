@@ -0,0 +1,165 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use chipper8::dap::{self, DapServer};
+
+mod common;
+
+const SOURCE: &str = ": main
+  v0 := 1
+  sub
+  v2 := 3
+  loop again
+: sub
+  v1 := 2
+  return
+";
+
+struct Session {
+    server: DapServer<Vec<u8>>,
+    sequence: u64,
+}
+
+impl Session {
+    fn new() -> Self {
+        Self { server: DapServer::new(Vec::new()), sequence: 0 }
+    }
+
+    // sends a framed request, runs the machine until it stops and returns the messages sent back
+    fn request(&mut self, command: &str, arguments: Value) -> Vec<Value> {
+        self.sequence += 1;
+        let mut framed = Vec::new();
+        dap::write_message(&mut framed, &json!({
+            "seq": self.sequence,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        })).unwrap();
+        let request = dap::read_message(&mut framed.as_slice()).unwrap().unwrap();
+        self.server.handle(&request).unwrap();
+        while self.server.is_running() {
+            self.server.run(100).unwrap();
+        }
+        let output = std::mem::take(self.server.writer_mut());
+        let mut reader = output.as_slice();
+        let mut messages = Vec::new();
+        while let Some(message) = dap::read_message(&mut reader).unwrap() {
+            messages.push(message);
+        }
+        let response = &messages[0];
+        assert_eq!(response["type"], "response");
+        assert_eq!(response["command"], command);
+        assert_eq!(response["request_seq"], self.sequence);
+        messages
+    }
+
+    fn body(&mut self, command: &str, arguments: Value) -> Value {
+        let messages = self.request(command, arguments);
+        assert_eq!(messages[0]["success"], true, "{} failed: {}", command, messages[0]);
+        messages[0]["body"].clone()
+    }
+
+    // the reason given by the stopped event a request caused
+    fn stop_reason(&mut self, command: &str, arguments: Value) -> String {
+        let messages = self.request(command, arguments);
+        let stopped = messages.iter().find(|message| message["event"] == "stopped").expect("no stopped event");
+        String::from(stopped["body"]["reason"].as_str().unwrap())
+    }
+
+    fn top_frame(&mut self) -> Value {
+        self.body("stackTrace", json!({ "threadId": 1 }))["stackFrames"][0].clone()
+    }
+}
+
+fn source_path(name: &str) -> PathBuf {
+    common::temp_file(&format!("{}.8o", name), SOURCE).canonicalize().unwrap()
+}
+
+fn launch(session: &mut Session, path: &Path) {
+    session.body("initialize", json!({ "adapterID": "chipper8" }));
+    let messages = session.request("launch", json!({ "program": path, "stopOnEntry": true }));
+    assert!(messages.iter().any(|message| message["event"] == "initialized"));
+}
+
+#[test]
+fn test_breakpoints_and_stepping() {
+    let path = source_path("breakpoints_and_stepping");
+    let mut session = Session::new();
+    launch(&mut session, &path);
+    let breakpoints = session.body("setBreakpoints", json!({
+        "source": { "path": path },
+        "breakpoints": [{ "line": 6 }, { "line": 100 }],
+    }));
+    // the label line has no code: the breakpoint moves to the line after it
+    assert_eq!(breakpoints["breakpoints"][0]["verified"], true);
+    assert_eq!(breakpoints["breakpoints"][0]["line"], 7);
+    assert_eq!(breakpoints["breakpoints"][1]["verified"], false);
+    assert_eq!(session.stop_reason("configurationDone", json!({})), "entry");
+    assert_eq!(session.top_frame()["line"], 2);
+
+    assert_eq!(session.stop_reason("continue", json!({ "threadId": 1 })), "breakpoint");
+    let frames = session.body("stackTrace", json!({ "threadId": 1 }))["stackFrames"].clone();
+    assert_eq!(frames.as_array().unwrap().len(), 2);
+    assert_eq!(frames[0]["name"], "sub");
+    assert_eq!(frames[0]["line"], 7);
+    assert_eq!(frames[0]["source"]["path"], json!(path));
+    // callers are shown at their call
+    assert_eq!(frames[1]["line"], 3);
+
+    assert_eq!(session.stop_reason("stepOut", json!({ "threadId": 1 })), "step");
+    assert_eq!(session.top_frame()["line"], 4);
+    assert_eq!(session.stop_reason("next", json!({ "threadId": 1 })), "step");
+    assert_eq!(session.top_frame()["line"], 5);
+    session.body("disconnect", json!({}));
+    assert!(session.server.is_terminated());
+}
+
+#[test]
+fn test_step_over_call() {
+    let path = source_path("step_over_call");
+    let mut session = Session::new();
+    launch(&mut session, &path);
+    session.body("configurationDone", json!({}));
+    assert_eq!(session.stop_reason("stepIn", json!({ "threadId": 1 })), "step");
+    assert_eq!(session.top_frame()["line"], 3);
+    assert_eq!(session.stop_reason("next", json!({ "threadId": 1 })), "step");
+    assert_eq!(session.top_frame()["line"], 4);
+    assert_eq!(session.server.repl.machine.registers[1], 2);
+}
+
+#[test]
+fn test_variables_and_memory() {
+    let path = source_path("variables_and_memory");
+    let mut session = Session::new();
+    launch(&mut session, &path);
+    session.body("setBreakpoints", json!({ "source": { "path": path }, "breakpoints": [{ "line": 8 }] }));
+    session.request("configurationDone", json!({}));
+    session.stop_reason("continue", json!({ "threadId": 1 }));
+
+    let scopes = session.body("scopes", json!({ "frameId": 0 }))["scopes"].clone();
+    let names: Vec<_> = scopes.as_array().unwrap().iter().map(|scope| scope["name"].clone()).collect();
+    assert_eq!(names, ["Registers", "Timers", "Stack"]);
+    let variables = |session: &mut Session, scope: usize| {
+        let reference = scopes[scope]["variablesReference"].clone();
+        session.body("variables", json!({ "variablesReference": reference }))["variables"].clone()
+    };
+    let registers = variables(&mut session, 0);
+    assert_eq!(registers[0], json!({ "name": "V0", "value": "0x01", "variablesReference": 0 }));
+    assert_eq!(registers[1]["value"], "0x02");
+    assert_eq!(registers[16]["name"], "I");
+    assert_eq!(variables(&mut session, 1).as_array().unwrap().len(), 2);
+    let stack = variables(&mut session, 2);
+    assert_eq!(stack[0]["value"], "1");
+    assert_eq!(stack[1]["value"], "0x204 (main+4)");
+
+    // v0 := 1
+    let memory = session.body("readMemory", json!({ "memoryReference": "0x200", "count": 2 }));
+    assert_eq!(memory["data"], "YAE=");
+    assert_eq!(memory["unreadableBytes"], 0);
+    let memory = session.body("readMemory", json!({ "memoryReference": "0xFFE", "offset": 1, "count": 2 }));
+    assert_eq!(memory["unreadableBytes"], 1);
+
+    let messages = session.request("evaluate", json!({ "expression": "v0" }));
+    assert_eq!(messages[0]["success"], false);
+}
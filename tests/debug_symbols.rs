@@ -0,0 +1,71 @@
+use std::path::Path;
+
+use chipper8::Error;
+use chipper8::assembler::Symbols;
+use chipper8::repl::Repl;
+use chipper8::storage::{MemoryStorage, Storage};
+use chipper8::ui::Rom;
+
+mod common;
+
+const SOURCE: &str = ": main
+  v0 := 1
+  count
+  loop again
+: count
+  v0 += 1
+  return
+";
+
+#[test]
+fn test_break_at_label() {
+    let mut repl = common::load_source("count.8o", SOURCE);
+    for command in [":break count", ":play"] {
+        repl.state.parse_command(command);
+        repl.execute_buffered();
+    }
+    while repl.state.running {
+        repl.step_running();
+    }
+    assert!(matches!(repl.state.error, Some(Error::Breakpoint(_))));
+    assert_eq!(repl.machine.program_counter.as_index(), 0x206);
+    assert_eq!(repl.state.describe(0x206).as_deref(), Some("count (line 6: v0 += 1)"));
+}
+
+#[test]
+fn test_symbols_file_round_trip() {
+    let program = chipper8::assembler::compile(SOURCE).unwrap();
+    let symbols = Symbols::new(&program, None, SOURCE);
+    let path = common::temp_dir("round_trip").join("count.sym");
+    symbols.save(&path).unwrap();
+    let loaded = Symbols::load(&path).unwrap();
+    assert_eq!(loaded.labels, symbols.labels);
+    assert_eq!(loaded.lines, symbols.lines);
+}
+
+#[test]
+fn test_line_zero_has_no_source() {
+    let program = chipper8::assembler::compile(SOURCE).unwrap();
+    let mut symbols = Symbols::new(&program, None, SOURCE);
+    symbols.lines.insert(0x200, 0);
+    assert_eq!(symbols.source_at(0x200), None);
+    assert_eq!(symbols.source_at(0x202), Some("count"));
+}
+
+#[test]
+fn test_broken_symbols_are_ignored() {
+    let mut storage = MemoryStorage::new();
+    let path = Path::new("roms/count.ch8");
+    storage.write(path, &[0x60, 0x01, 0x12, 0x02]).unwrap();
+    storage.write(&Symbols::path_for(path), b"{ not json").unwrap();
+    let rom = Rom::from_storage(&storage, path).unwrap();
+    assert!(rom.symbols.is_none());
+    assert_eq!(rom.warnings.len(), 1);
+
+    let mut repl = Repl::new();
+    repl.storage = Box::new(storage);
+    repl.state.parse_command(":load roms/count.ch8");
+    repl.execute_buffered();
+    assert!(repl.state.rom.is_some());
+    assert!(matches!(repl.state.error, Some(Error::Warning(_))));
+}
@@ -0,0 +1,114 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chipper8::dump::{ScreenDump, ScreenFormat, StateSelection};
+use chipper8::{Emulator, EmulatorConfig};
+
+// v0 := 0; i := hex v0; sprite v0 v0 5; exit
+const PROGRAM: [u8; 8] = [0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05, 0x00, 0xF0];
+
+fn output_path(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join("chipper8_dump");
+    fs::create_dir_all(&directory).unwrap();
+    directory.join(name)
+}
+
+fn run(config: EmulatorConfig) -> Emulator {
+    let mut emulator = Emulator::from_bytes("test", PROGRAM.to_vec(), EmulatorConfig { turbo: true, ..config });
+    emulator.run().unwrap();
+    emulator
+}
+
+#[test]
+fn test_screen_formats() {
+    let mut display = vec![0; 64 * 32];
+    display[0] = 1;
+    display[63 + 64] = 1;
+    let ascii = String::from_utf8(ScreenFormat::Ascii.encode(&display).unwrap()).unwrap();
+    let lines: Vec<_> = ascii.lines().collect();
+    assert_eq!(lines.len(), 32);
+    assert_eq!(lines[0], format!("#{}", ".".repeat(63)));
+    assert_eq!(lines[1], format!("{}#", ".".repeat(63)));
+
+    let hex = String::from_utf8(ScreenFormat::Hex.encode(&display).unwrap()).unwrap();
+    let lines: Vec<_> = hex.lines().collect();
+    assert_eq!(lines[..3], ["8000000000000000", "0000000000000001", "0000000000000000"]);
+
+    let pbm = String::from_utf8(ScreenFormat::Pbm.encode(&display).unwrap()).unwrap();
+    assert!(pbm.starts_with("P1\n64 32\n1 0 0 "));
+
+    let png = ScreenFormat::Png.encode(&display).unwrap();
+    assert_eq!(png[..8], [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n']);
+}
+
+#[test]
+fn test_screen_dump_paths() {
+    let screen_dump = ScreenDump::new("out/screen.png");
+    assert_eq!(screen_dump.format, ScreenFormat::Png);
+    assert_eq!(screen_dump.frame_path(60), Path::new("out/screen-000060.png"));
+    assert!(!screen_dump.is_due(60));
+    assert_eq!(ScreenDump::new("screen").format, ScreenFormat::Ascii);
+    let screen_dump = ScreenDump { every: Some(30), ..ScreenDump::new("screen.hex") };
+    assert!(screen_dump.is_due(60));
+    assert!(!screen_dump.is_due(61));
+}
+
+#[test]
+fn test_final_screen_dump() {
+    let path = output_path("final_screen.hex");
+    let _ = fs::remove_file(&path);
+    run(EmulatorConfig { screen_dump: Some(ScreenDump::new(&path)), ..EmulatorConfig::new() });
+    let hex = fs::read_to_string(&path).unwrap();
+    // the top of the sprite for 0
+    assert!(hex.starts_with("f000000000000000\n9000000000000000\n"));
+}
+
+#[test]
+fn test_selective_state_dump() {
+    let path = output_path("selective_state.json");
+    let selection = StateSelection { registers: true, memory: vec![0x200..0x202, 0x202..0x204] };
+    let emulator = run(EmulatorConfig { dump_path: Some(path.clone()), dump_selection: selection, ..EmulatorConfig::new() });
+    let dump: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    let keys: Vec<_> = dump.as_object().unwrap().keys().cloned().collect();
+    assert_eq!(keys, ["delay_timer", "index", "memory", "program_counter", "registers", "sound_timer", "stack"]);
+    assert_eq!(dump["registers"], serde_json::to_value(&emulator.machine.registers).unwrap());
+    assert_eq!(dump["memory"], serde_json::json!([
+        { "start": 0x200, "bytes": "YAA=" },
+        { "start": 0x202, "bytes": "8Ck=" },
+    ]));
+
+    // nothing selected dumps everything
+    let full = StateSelection::new().dump(&emulator.machine).unwrap();
+    assert_eq!(full, serde_json::to_value(&emulator.machine).unwrap());
+}
+
+#[test]
+fn test_intermediate_screen_dumps() {
+    let path = output_path("every.txt");
+    let screen_dump = ScreenDump { every: Some(2), ..ScreenDump::new(&path) };
+    let frame_paths: Vec<_> = (1..=4).map(|frame| screen_dump.frame_path(frame)).collect();
+    for frame_path in &frame_paths {
+        let _ = fs::remove_file(frame_path);
+    }
+    // one instruction per frame
+    let emulator = run(EmulatorConfig { fps: 60, screen_dump: Some(screen_dump), ..EmulatorConfig::new() });
+    assert_eq!(emulator.frames, 4);
+    let written: Vec<_> = frame_paths.iter().map(|frame_path| frame_path.exists()).collect();
+    assert_eq!(written, [false, true, false, true]);
+    // the sprite is drawn in the third frame
+    assert!(!fs::read_to_string(&frame_paths[1]).unwrap().contains('#'));
+    assert!(fs::read_to_string(&frame_paths[3]).unwrap().starts_with("####."));
+}
+
+#[test]
+fn test_coverage() {
+    let path = output_path("coverage.json");
+    let emulator = run(EmulatorConfig { coverage_path: Some(path.clone()), ..EmulatorConfig::new() });
+    assert_eq!(emulator.rom_range, 0x200..0x208);
+    let coverage: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(coverage["start"], 0x200);
+    assert_eq!(coverage["size"], 8);
+    assert_eq!(coverage["executed_instructions"], 4);
+    // the font sprite is read from outside the ROM
+    assert_eq!(coverage["read_bytes"], 0);
+}
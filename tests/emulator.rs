@@ -0,0 +1,178 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::rc::Rc;
+use std::time::Duration;
+
+use chipper8::{Emulator, EmulatorConfig, RunOutcome};
+use chipper8::machine::Address;
+use chipper8::repl::Repl;
+
+mod common;
+
+// v0 := 1; v1 := key; exit
+const PROGRAM: [u8; 6] = [0x60, 0x01, 0xF1, 0x0A, 0x00, 0xF0];
+
+fn recording_emulator(bytes: &[u8]) -> (Emulator, Rc<RefCell<Vec<String>>>) {
+    let mut emulator = Emulator::from_bytes("test", bytes.to_vec(), EmulatorConfig::new());
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let recorded = events.clone();
+    emulator.on_event(move |event| recorded.borrow_mut().push(event.to_string()));
+    (emulator, events)
+}
+
+#[test]
+fn test_run_outcomes() {
+    let (mut emulator, events) = recording_emulator(&PROGRAM);
+    assert!(matches!(emulator.step(), RunOutcome::WaitingForKey));
+    assert!(matches!(emulator.step_instructions(10), RunOutcome::WaitingForKey));
+    // waiting is reported once
+    assert_eq!(*events.borrow(), ["waiting for a key press"]);
+    emulator.machine.key_buffer = Some(0xA);
+    assert!(matches!(emulator.step(), RunOutcome::Running));
+    assert_eq!(emulator.machine.registers[1], 0xA);
+    assert!(matches!(emulator.step_frame(), RunOutcome::Exited));
+    assert_eq!(events.borrow().last().unwrap(), "machine exited");
+}
+
+// delay := 60; loop: jump loop
+const TIMER_PROGRAM: [u8; 6] = [0x60, 0x3C, 0xF0, 0x15, 0x12, 0x04];
+
+#[test]
+fn test_timers_count_per_frame() {
+    // at 60Hz however many instructions run per frame
+    for (fps, speed) in [(120, 1.0), (600, 1.0), (120, 2.5)] {
+        let config = EmulatorConfig { fps, speed, ..EmulatorConfig::new() };
+        let mut emulator = Emulator::from_bytes("timer", TIMER_PROGRAM.to_vec(), config);
+        for _ in 0..31 {
+            emulator.step_frame();
+        }
+        // set during the first frame
+        assert_eq!(emulator.machine.delay_timer, 30);
+    }
+}
+
+#[test]
+fn test_repl_speed_and_turbo() {
+    let mut repl = Repl::new();
+    repl.machine.load_program(&Address::try_from(0x200u16).unwrap(), &TIMER_PROGRAM);
+    repl.state.running = true;
+    // the first frame runs straight away, with 60 instructions per second times 2
+    repl.state.speed = 2.0;
+    repl.run_frames();
+    assert_eq!(repl.machine.program_counter, Address::try_from(0x204u16).unwrap());
+    assert_eq!(repl.machine.delay_timer, 60);
+    // turbo runs a frame's time worth of frames at once
+    repl.state.turbo = true;
+    repl.run_frames();
+    assert!(repl.machine.delay_timer < 60);
+}
+
+#[test]
+fn test_breakpoint() {
+    let (mut emulator, _) = recording_emulator(&PROGRAM);
+    emulator.breakpoints.insert(0x202);
+    assert!(matches!(emulator.step_instructions(10), RunOutcome::Breakpoint(0x202)));
+}
+
+#[test]
+fn test_fault() {
+    let (mut emulator, events) = recording_emulator(&[0x60, 0x01, 0x00, 0x00]);
+    match emulator.run().unwrap() {
+        RunOutcome::Fault { address, error } => {
+            assert_eq!(address, 0x202);
+            assert_eq!(error.to_string(), "invalid opcode: 0x0000");
+        }
+        outcome => panic!("unexpected outcome: {}", outcome),
+    }
+    assert!(emulator.terminated);
+    assert_eq!(events.borrow().len(), 1);
+
+    let (mut emulator, events) = recording_emulator(&[0x00, 0x00, 0x00, 0xF0]);
+    emulator.config.skip_unknown_opcode = true;
+    assert!(matches!(emulator.step_instructions(10), RunOutcome::Exited));
+    assert_eq!(events.borrow()[0], "skipped invalid opcode: 0x0000 at 0x200");
+}
+
+#[test]
+fn test_limits() {
+    // loop: jump loop
+    let spin = [0x12, 0x00];
+    let (mut emulator, _) = recording_emulator(&spin);
+    emulator.config.max_ticks = Some(100);
+    emulator.config.turbo = true;
+    let outcome = emulator.run().unwrap();
+    assert!(matches!(outcome, RunOutcome::TickLimit));
+    assert_eq!(outcome.exit_code(), 2);
+    assert_eq!(emulator.ticks, 100);
+
+    let (mut emulator, _) = recording_emulator(&spin);
+    emulator.config.max_duration = Some(Duration::from_millis(50));
+    emulator.config.turbo = true;
+    assert!(matches!(emulator.run().unwrap(), RunOutcome::TimeLimit));
+
+    let (mut emulator, _) = recording_emulator(&spin);
+    emulator.machine.config.auto_exit = true;
+    assert_eq!(emulator.run().unwrap().exit_code(), 0);
+}
+
+// runs the emulator binary headless in `directory`, with its settings kept out of the user's
+// configuration
+fn headless_output(directory: &Path, rom: &[u8], arguments: &[&str]) -> Output {
+    let path = directory.join("rom.ch8");
+    fs::write(&path, rom).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_chipper8"))
+        .arg("--headless")
+        .arg("--turbo")
+        .arg(&path)
+        .args(arguments)
+        .env("HOME", directory)
+        .env("XDG_CONFIG_HOME", directory)
+        .output()
+        .unwrap()
+}
+
+fn run_headless_in(directory: &Path, rom: &[u8], arguments: &[&str]) -> Option<i32> {
+    headless_output(directory, rom, arguments).status.code()
+}
+
+fn run_headless(rom: &[u8], arguments: &[&str]) -> Option<i32> {
+    run_headless_in(&common::temp_dir("headless"), rom, arguments)
+}
+
+#[test]
+fn test_headless_exit_codes() {
+    assert_eq!(run_headless(&PROGRAM, &["--until-pc", "0x202"]), Some(0));
+    assert_eq!(run_headless(&PROGRAM, &[]), Some(3));
+    assert_eq!(run_headless(&[0x00, 0x00], &[]), Some(1));
+    // loop: jump loop
+    assert_eq!(run_headless(&[0x12, 0x00], &["--auto-exit"]), Some(0));
+    assert_eq!(run_headless(&[0x12, 0x00], &["--max-ticks", "1000"]), Some(2));
+    assert_eq!(run_headless(&[0x12, 0x00], &["--max-seconds", "0.1"]), Some(2));
+}
+
+#[test]
+fn test_headless_options() {
+    let directory = common::temp_dir("headless");
+    fs::create_dir_all(directory.join("chipper8")).unwrap();
+    fs::write(directory.join("chipper8/settings.json"), r#"{ "skip_unknown_opcode": true }"#).unwrap();
+    // the saved `true` skips invalid opcodes until the tick limit, unless turned off again
+    assert_eq!(run_headless_in(&directory, &[0x00, 0x00], &["--max-ticks", "10"]), Some(2));
+    assert_eq!(run_headless_in(&directory, &[0x00, 0x00], &["--skip-unknown-opcode=false"]), Some(1));
+    assert_eq!(run_headless(&[0x00, 0x00], &["--skip-unknown-opcode", "--max-ticks", "10"]), Some(2));
+    // rejected by the argument parser with its usage error code (there's no limit to hit), rather
+    // than running no instructions at all
+    let output = headless_output(&common::temp_dir("headless"), &PROGRAM, &["--fps", "0"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid value '0' for '--fps"));
+    // frame rates beyond the settings slider's are fine
+    assert_eq!(run_headless(&PROGRAM, &["--fps", "1000", "--until-pc", "0x202"]), Some(0));
+}
+
+#[test]
+fn test_headless_leaves_settings_alone() {
+    let directory = common::temp_dir("headless");
+    assert_eq!(run_headless_in(&directory, &PROGRAM, &["--until-pc", "0x202"]), Some(0));
+    assert!(!directory.join("chipper8/settings.json").exists());
+}
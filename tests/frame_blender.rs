@@ -0,0 +1,52 @@
+use chipper8::ui::util::{BlendMode, FrameBlender};
+
+const ON: [u8; 4] = [0xFF, 0xFF, 0x00, 0x00];
+const OFF: [u8; 4] = [0x00; 4];
+
+fn blender(mode: BlendMode) -> FrameBlender {
+    let mut blender = FrameBlender::new(4);
+    blender.mode = mode;
+    blender
+}
+
+#[test]
+fn test_raw() {
+    let mut blender = blender(BlendMode::Raw);
+    assert_eq!(blender.blend(&ON), ON);
+    assert_eq!(blender.blend(&OFF), OFF);
+}
+
+#[test]
+fn test_decay() {
+    let mut blender = blender(BlendMode::Decay);
+    blender.persistence = 0.5;
+    assert_eq!(blender.blend(&ON), ON);
+    // pixels switched off fade out, pixels switched on light up straight away
+    assert_eq!(blender.blend(&OFF), [0x7F, 0x7F, 0x00, 0x00]);
+    assert_eq!(blender.blend(&OFF), [0x3F, 0x3F, 0x00, 0x00]);
+    assert_eq!(blender.blend(&[0x00, 0xFF, 0xFF, 0x00]), [0x1F, 0xFF, 0xFF, 0x00]);
+}
+
+#[test]
+fn test_or() {
+    let mut blender = blender(BlendMode::Or);
+    blender.frames = 2;
+    assert_eq!(blender.blend(&ON), ON);
+    assert_eq!(blender.blend(&[0x00, 0x00, 0xFF, 0x00]), [0xFF, 0xFF, 0xFF, 0x00]);
+    // only the last two frames count
+    assert_eq!(blender.blend(&OFF), [0x00, 0x00, 0xFF, 0x00]);
+    assert_eq!(blender.blend(&OFF), OFF);
+}
+
+#[test]
+fn test_update_once_per_frame() {
+    let mut blender = blender(BlendMode::Decay);
+    blender.persistence = 0.5;
+    blender.update(1, &ON);
+    assert_eq!(blender.update(2, &OFF), [0x7F, 0x7F, 0x00, 0x00]);
+    // redrawing without a new frame doesn't fade any further
+    assert_eq!(blender.update(2, &OFF), [0x7F, 0x7F, 0x00, 0x00]);
+    assert_eq!(blender.update(3, &OFF), [0x3F, 0x3F, 0x00, 0x00]);
+    // but a display changed without running a frame (single-stepping) is shown
+    assert_eq!(blender.update(3, &ON), ON);
+}
@@ -0,0 +1,147 @@
+use std::collections::BTreeSet;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+use chipper8::gdb::GdbServer;
+use chipper8::machine::Address;
+use chipper8::Machine;
+
+// v0 := 5; loop: v0 += 1; jump loop
+const PROGRAM: [u8; 6] = [0x60, 0x05, 0x70, 0x01, 0x12, 0x02];
+
+struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+    fn send(&mut self, data: &str) {
+        let checksum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        write!(self.stream, "${}#{:02x}", data, checksum).unwrap();
+    }
+
+    fn request(&mut self, data: &str) -> String {
+        self.send(data);
+        self.reply()
+    }
+
+    fn reply(&mut self) -> String {
+        let mut packet = Vec::new();
+        let mut byte = [0];
+        // skip acknowledgements up to the start of the reply
+        while byte[0] != b'$' {
+            self.stream.read_exact(&mut byte).unwrap();
+        }
+        loop {
+            self.stream.read_exact(&mut byte).unwrap();
+            if byte[0] == b'#' {
+                break;
+            }
+            packet.push(byte[0]);
+        }
+        let mut checksum = [0; 2];
+        self.stream.read_exact(&mut checksum).unwrap();
+        String::from_utf8(packet).unwrap()
+    }
+}
+
+// runs the client in a thread while polling the server, which owns the machine, in this one
+fn debug(session: impl FnOnce(&mut Client) + Send + 'static) -> (Machine, BTreeSet<u16>) {
+    let mut machine = Machine::new();
+    let start = Address::try_from(0x200_u16).unwrap();
+    machine.load(&start, &PROGRAM);
+    machine.program_counter = start;
+    let mut breakpoints = BTreeSet::new();
+    let mut server = GdbServer::bind(0).unwrap();
+    let address = server.local_addr().unwrap();
+    let client = thread::spawn(move || {
+        let stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        stream.set_nodelay(true).unwrap();
+        session(&mut Client { stream });
+    });
+    while !client.is_finished() {
+        server.poll(&mut machine, &mut breakpoints, 1).unwrap();
+        thread::sleep(Duration::from_millis(1));
+    }
+    client.join().unwrap();
+    (machine, breakpoints)
+}
+
+#[test]
+fn test_registers() {
+    let (machine, _) = debug(|client| {
+        assert_eq!(client.request("?"), "S05");
+        // V0-VF, then I and PC (little endian), SP, DT and ST
+        assert_eq!(client.request("g"), format!("{}{}{}", "00".repeat(16), "0000", "0002000000"));
+        assert_eq!(client.request("p11"), "0002");
+        assert_eq!(client.request("P3=2a"), "OK");
+        assert_eq!(client.request("P10=3403"), "OK");
+        assert_eq!(client.request("P11=0010"), "E01");
+        assert_eq!(client.request("p3"), "2a");
+    });
+    assert_eq!(machine.registers[3], 0x2a);
+    assert_eq!(u16::from(&machine.index), 0x334);
+    assert_eq!(u16::from(&machine.program_counter), 0x200);
+}
+
+#[test]
+fn test_memory() {
+    let (machine, _) = debug(|client| {
+        assert_eq!(client.request("m200,6"), "600570011202");
+        assert_eq!(client.request("M300,2:abcd"), "OK");
+        assert_eq!(client.request("m300,2"), "abcd");
+        assert_eq!(client.request("mfff,2"), "E01");
+    });
+    assert_eq!(machine.memory[0x300..0x302], [0xab, 0xcd]);
+}
+
+#[test]
+fn test_breakpoints_and_stepping() {
+    let (machine, breakpoints) = debug(|client| {
+        assert_eq!(client.request("s"), "S05");
+        assert_eq!(client.request("p11"), "0202");
+        assert_eq!(client.request("Z0,204,2"), "OK");
+        assert_eq!(client.request("c"), "S05");
+        assert_eq!(client.request("p0"), "06");
+        // continuing from a breakpoint runs the instruction there first
+        assert_eq!(client.request("c"), "S05");
+        assert_eq!(client.request("p0"), "07");
+        assert_eq!(client.request("z0,204,2"), "OK");
+        assert_eq!(client.request("Z1,202,2"), "OK");
+        assert_eq!(client.request("c"), "S05");
+        assert_eq!(client.request("p11"), "0202");
+    });
+    assert_eq!(breakpoints, BTreeSet::from([0x202]));
+    assert_eq!(machine.registers[0], 7);
+}
+
+#[test]
+fn test_interrupt() {
+    debug(|client| {
+        client.send("c");
+        thread::sleep(Duration::from_millis(20));
+        client.stream.write_all(&[0x03]).unwrap();
+        assert_eq!(client.reply(), "S02");
+        assert_eq!(client.request("D"), "OK");
+    });
+}
+
+#[test]
+fn test_target_description() {
+    debug(|client| {
+        assert!(client.request("qSupported:xmlRegisters=i386").contains("qXfer:features:read+"));
+        let mut description = String::new();
+        loop {
+            let chunk = client.request(&format!("qXfer:features:read:target.xml:{:x},40", description.len()));
+            description.push_str(&chunk[1..]);
+            if chunk.starts_with('l') {
+                break;
+            }
+        }
+        assert!(description.contains("<reg name=\"vf\" bitsize=\"8\" regnum=\"15\""));
+        assert!(description.contains("<reg name=\"pc\" bitsize=\"16\" regnum=\"17\" type=\"code_ptr\"/>"));
+        assert!(description.ends_with("</target>\n"));
+    });
+}
@@ -0,0 +1,50 @@
+use chipper8::input::{Button, ScriptedInput};
+use chipper8::ui::KeyCapture;
+use chipper8::ui::util::key_capture::KeyBindings;
+
+#[test]
+fn test_scripted_input() {
+    let mut key_capture = KeyCapture::new();
+    key_capture.sources.push(Box::new(ScriptedInput::new([
+        (2, vec![]),
+        (1, vec![0x4]),
+        (2, vec![0xA, 0x5]),
+    ])));
+    let mut pressed = Vec::new();
+    for _ in 0..6 {
+        key_capture.keys.fill(false);
+        key_capture.poll_sources();
+        pressed.push(key_capture.key());
+    }
+    assert_eq!(pressed, vec![None, None, Some(0x4), Some(0x5), Some(0x5), None]);
+}
+
+#[test]
+fn test_duplicate_keymap() {
+    let mut bindings = KeyBindings::new();
+    assert_eq!(bindings.duplicate("Mine"), "Mine");
+    // existing keymaps are never overwritten
+    assert_eq!(bindings.duplicate("Mine"), "Mine 2");
+    assert_eq!(bindings.duplicate("Mine"), "Mine 3");
+    assert_eq!(bindings.effective(), "Mine 3");
+    assert_eq!(bindings.available_bindings().len(), 5);
+}
+
+#[test]
+fn test_rom_button_maps() {
+    let mut bindings = KeyBindings::new();
+    bindings.set_rom(Some("pong.ch8"));
+    assert!(!bindings.has_rom_button_map());
+    bindings.set_rom_button_map(true);
+    assert!(bindings.has_rom_button_map());
+    // edits go to the ROM's map only
+    bindings.bind_button(Button::South, Some(0x1));
+    bindings.bind_button(Button::Start, None);
+    assert_eq!(bindings.button_map().get(&Button::South), Some(&0x1));
+    assert_eq!(bindings.button_map().get(&Button::Start), None);
+    bindings.set_rom(None);
+    assert_eq!(bindings.button_map().get(&Button::South), Some(&0x5));
+    bindings.set_rom(Some("pong.ch8"));
+    bindings.set_rom_button_map(false);
+    assert_eq!(bindings.button_map().get(&Button::South), Some(&0x5));
+}
@@ -0,0 +1,30 @@
+use std::fs;
+
+use chipper8::library::{Platform, RomLibrary};
+
+mod common;
+
+#[cfg(unix)]
+#[test]
+fn test_unreadable_files_skipped() {
+    let directory = common::temp_dir("library");
+    fs::write(directory.join("good.ch8"), [0x12, 0x00]).unwrap();
+    // a link to nothing can't be read
+    std::os::unix::fs::symlink(directory.join("missing"), directory.join("broken.ch8")).unwrap();
+    let mut library = RomLibrary::new();
+    library.scan(&[&directory]);
+    assert_eq!(library.entries.len(), 1);
+    assert_eq!(library.warnings.len(), 1);
+    assert!(library.warnings[0].contains("broken.ch8"));
+}
+
+#[test]
+fn test_bundled_roms_identified() {
+    let mut library = RomLibrary::new();
+    library.scan(&["roms"]);
+    assert_eq!(library.entries.len(), 4);
+    for entry in &library.entries {
+        let info = entry.info.as_ref().unwrap_or_else(|| panic!("{} not in database", entry.path.display()));
+        assert_eq!(info.platform, Platform::Chip8);
+    }
+}
@@ -4,10 +4,10 @@ use chipper8::{Emulator, EmulatorConfig, Machine};
 
 fn test_state(name: &str) {
     let rom_path = format!("tests/roms/{}.rom", name);
-    let mut emulator = Emulator::new(EmulatorConfig {
-        rom_path: rom_path.into(),
+    let mut emulator = Emulator::from_file(rom_path, EmulatorConfig {
         fps: 1000,
-        dump_path: None,
+        turbo: true,
+        ..EmulatorConfig::new()
     }).unwrap();
     emulator.machine.config.auto_exit = true;
     emulator.run().unwrap();
@@ -0,0 +1,37 @@
+use chipper8::repl::Repl;
+use chipper8::ui::MemoryTag;
+
+mod common;
+
+fn execute(repl: &mut Repl, command: &str) {
+    repl.state.parse_command(command);
+    repl.execute_buffered();
+    assert!(repl.state.error.is_none(), "{}: {:?}", command, repl.state.error);
+}
+
+#[test]
+fn test_user_tags_persist_per_rom() {
+    let mut repl = Repl::new();
+    execute(&mut repl, ":load ibm");
+    execute(&mut repl, r#":tag 0x22A 0x230 "logo sprites""#);
+    let tag = MemoryTag::User { name: String::from("logo sprites") };
+    assert_eq!(repl.state.memory_tags.get(&tag), Some(&(0x22A..0x230)));
+
+    let settings = repl.state.settings();
+    let mut restored = Repl::new();
+    restored.state.apply_settings(&settings);
+    execute(&mut restored, ":load ibm");
+    assert_eq!(restored.state.memory_tags.get(&tag), Some(&(0x22A..0x230)));
+    execute(&mut restored, ":unload");
+    assert!(!restored.state.memory_tags.contains_key(&tag));
+    execute(&mut restored, ":load ibm");
+    execute(&mut restored, r#":untag "logo sprites""#);
+    assert!(restored.state.settings().rom_tags["ibm.rom"].is_empty());
+}
+
+#[test]
+fn test_label_tags_from_symbols() {
+    let repl = common::load_source("labels.8o", ": main\n  loop again\n: data\n  1 2 3\n");
+    assert_eq!(repl.state.memory_tags.get(&MemoryTag::Label { name: String::from("main") }), Some(&(0x200..0x202)));
+    assert_eq!(repl.state.memory_tags.get(&MemoryTag::Label { name: String::from("data") }), Some(&(0x202..0x205)));
+}
@@ -0,0 +1,38 @@
+use std::fs;
+
+use chipper8::Error;
+use chipper8::machine::Coverage;
+use chipper8::repl::Repl;
+
+fn execute(repl: &mut Repl, command: &str) {
+    repl.state.parse_command(command);
+    repl.execute_buffered();
+    assert!(repl.state.error.is_none(), "{}: {:?}", command, repl.state.error);
+}
+
+#[test]
+fn test_coverage_report() {
+    let mut repl = Repl::new();
+    execute(&mut repl, ":profile on");
+    execute(&mut repl, ":load tests/roms/corax89.rom");
+    repl.machine.config.auto_exit = true;
+    execute(&mut repl, ":play");
+    while repl.state.running {
+        repl.step_running();
+    }
+    assert!(matches!(repl.state.error, Some(Error::MachineExit)));
+    repl.state.error = None;
+
+    let path = std::env::temp_dir().join("chipper8_coverage.json");
+    execute(&mut repl, &format!(":coverage {}", path.display()));
+    let coverage: Coverage = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(coverage.executed, repl.coverage().unwrap().executed);
+    assert_eq!(coverage.start, 0x200);
+    assert!(coverage.executed_instructions > 100);
+    // the test ROM draws its result sprites, so some of it is read as data
+    assert!(coverage.read_bytes > 0);
+    assert!(coverage.covered_percent > 50.0);
+    let profiler = repl.machine.profiler.as_ref().unwrap();
+    assert_eq!(profiler.subroutines[&0x200].calls, 1);
+    assert_eq!(profiler.total_executions(), coverage.executed.values().sum::<u64>());
+}
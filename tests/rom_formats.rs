@@ -0,0 +1,85 @@
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+use egui::Color32;
+
+use chipper8::assembler;
+use chipper8::library::{self, OctoCartridge, Platform};
+
+// encodes a payload the way Octo does: 2 bits per pixel, most significant first
+fn cartridge(json: &str) -> Vec<u8> {
+    let mut bytes = (json.len() as u32).to_be_bytes().to_vec();
+    bytes.extend(json.as_bytes());
+    let mut pixels: Vec<u8> = bytes.iter()
+        .flat_map(|byte| [6, 4, 2, 0].map(|shift| (byte >> shift) & 0b11 | 0b100))
+        .collect();
+    let (width, height) = (32u16, 16u16);
+    let frame_size = width as usize * height as usize;
+    pixels.resize(pixels.len().div_ceil(frame_size) * frame_size, 0);
+    let palette: Vec<u8> = (0..8).flat_map(|i| [i * 32, i * 32, i * 32]).collect();
+    let mut gif = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut gif, width, height, &palette).unwrap();
+        for chunk in pixels.chunks(frame_size) {
+            let frame = gif::Frame {
+                width,
+                height,
+                buffer: Cow::Borrowed(chunk),
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&frame).unwrap();
+        }
+    }
+    gif
+}
+
+#[test]
+fn test_octo_cartridge() {
+    let gif = cartridge(r##"{
+        "program": ": main\n  loop again\n",
+        "options": {
+            "tickrate": 20,
+            "fillColor": "#FFCC00",
+            "shiftQuirks": false,
+            "loadStoreQuirks": true,
+            "jumpQuirks": false,
+            "maxSize": 3583
+        }
+    }"##);
+    let cartridge = OctoCartridge::decode(&gif).unwrap();
+    assert_eq!(cartridge.program, ": main\n  loop again\n");
+    assert_eq!(assembler::compile(&cartridge.program).unwrap().bytes, vec![0x12, 0x00]);
+    let options = cartridge.rom_options();
+    assert_eq!(options.platform, Platform::SuperChip);
+    assert_eq!(options.tickrate, Some(20));
+    assert_eq!(options.quirks.bitshift_ignore_y, Some(false));
+    assert_eq!(options.quirks.load_increment_index, Some(false));
+    assert_eq!(options.colors.unwrap().foreground, Color32::from_rgb(0xFF, 0xCC, 0x00));
+}
+
+// a cartridge written by a separate GIF encoder rather than `cartridge` above: a 128x64 label
+// image with the payload in its low bits, a 16 colour palette and all of Octo's options
+#[test]
+fn test_octo_cartridge_fixture() {
+    let gif = std::fs::read("tests/data/octo-cartridge.gif").unwrap();
+    let cartridge = OctoCartridge::decode(&gif).unwrap();
+    assert!(cartridge.program.starts_with("# a bouncing dot"));
+    let program = assembler::compile(&cartridge.program).unwrap();
+    assert_eq!(program.labels.get("main"), Some(&0x203));
+    let options = cartridge.rom_options();
+    assert_eq!(options.platform, Platform::SuperChip);
+    assert_eq!(options.tickrate, Some(20));
+    assert_eq!(options.quirks.bitshift_ignore_y, Some(true));
+    assert_eq!(options.quirks.load_increment_index, Some(false));
+    assert_eq!(options.quirks.jump_xnn, Some(false));
+    let colors = options.colors.unwrap();
+    assert_eq!(colors.foreground, Color32::from_rgb(0xFF, 0xCC, 0x00));
+    assert_eq!(colors.background, Color32::from_rgb(0x99, 0x66, 0x00));
+}
+
+#[test]
+fn test_find_rom() {
+    assert_eq!(library::find_rom("ibm"), Some(PathBuf::from("roms/ibm.rom")));
+    assert_eq!(library::find_rom("roms/tests/bc"), Some(PathBuf::from("roms/tests/bc.rom")));
+    assert_eq!(library::find_rom("missing"), None);
+}
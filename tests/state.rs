@@ -0,0 +1,113 @@
+use std::fs;
+
+use chipper8::{Emulator, EmulatorConfig, Machine};
+use chipper8::ui::Rom;
+use chipper8::state::{self, RomIdentity, STATE_VERSION};
+
+fn run_rom(name: &str) -> Emulator {
+    let mut emulator = Emulator::from_file(format!("tests/roms/{}.rom", name), EmulatorConfig {
+        fps: 1000,
+        turbo: true,
+        ..EmulatorConfig::new()
+    }).unwrap();
+    emulator.machine.config.auto_exit = true;
+    emulator.run().unwrap();
+    emulator
+}
+
+#[test]
+fn test_legacy_states_load() {
+    // the fixtures are bare machine states with memory and display as arrays of numbers
+    for name in ["exit", "bc", "corax89"] {
+        let path = format!("tests/data/{}.json", name);
+        let loaded = state::load(&path).unwrap();
+        assert_eq!(loaded.version, STATE_VERSION);
+        assert_eq!(loaded.rom, None);
+        let expected: Machine = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(loaded.machine, expected);
+    }
+}
+
+#[test]
+fn test_json_round_trip() {
+    let emulator = run_rom("corax89");
+    let json = state::to_json(&emulator.machine, Some(&emulator.rom_identity)).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["version"], STATE_VERSION);
+    assert_eq!(value["rom"]["name"], emulator.rom_name);
+    assert!(value["machine"]["memory"].is_string());
+    assert!(value["machine"]["display"].is_string());
+
+    let loaded = state::from_json(&json).unwrap();
+    assert_eq!(loaded.rom.as_ref(), Some(&emulator.rom_identity));
+    assert_eq!(loaded.machine, emulator.machine);
+}
+
+#[test]
+fn test_binary_round_trip() {
+    let mut emulator = run_rom("bc");
+    emulator.machine.key_buffer = Some(0xA);
+    emulator.machine.config.jump_xnn = true;
+    let bytes = state::to_bytes(&emulator.machine, Some(&emulator.rom_identity));
+    assert!(bytes.len() < 4096 + 512);
+    let loaded = state::from_bytes(&bytes).unwrap();
+    assert_eq!(loaded.rom.as_ref(), Some(&emulator.rom_identity));
+    assert_eq!(loaded.machine, emulator.machine);
+
+    let path = std::env::temp_dir().join("chipper8_state.c8s");
+    state::save(&path, &emulator.machine, None).unwrap();
+    assert_eq!(state::load(&path).unwrap().machine, emulator.machine);
+
+    assert!(state::from_bytes(&bytes[..100]).is_err());
+}
+
+#[test]
+fn test_migrations() {
+    // quirks missing from older states are off
+    let mut value: serde_json::Value = serde_json::from_str(&fs::read_to_string("tests/data/exit.json").unwrap()).unwrap();
+    value["config"].as_object_mut().unwrap().remove("jump_xnn");
+    let loaded = state::from_json(&value.to_string()).unwrap();
+    assert!(!loaded.machine.config.jump_xnn);
+
+    let future = serde_json::json!({ "version": STATE_VERSION + 1, "rom": null, "machine": value });
+    assert_eq!(state::from_json(&future.to_string()).unwrap_err().to_string(), "invalid save state: unsupported version 2");
+
+    let rom = RomIdentity { name: String::from("test"), sha1: String::from("0") };
+    let state = serde_json::json!({ "version": 1, "rom": rom, "machine": value });
+    assert_eq!(state::from_json(&state.to_string()).unwrap().rom, Some(rom));
+}
+
+#[test]
+fn test_invalid_sizes() {
+    let emulator = run_rom("exit");
+    let mut machine = Machine::new();
+    machine.registers.truncate(4);
+    let error = state::from_bytes(&state::to_bytes(&machine, None)).unwrap_err();
+    assert_eq!(error.to_string(), "invalid save state: registers has size 4, expected 16");
+
+    // the stack pointer is past the stack, or at slots holding no return address
+    for pointer in [17, 2] {
+        let mut machine = Machine::new();
+        machine.stack.pointer = pointer;
+        assert!(state::from_bytes(&state::to_bytes(&machine, None)).is_err());
+        assert!(state::from_json(&state::to_json(&machine, None).unwrap()).is_err());
+    }
+
+    let mut value: serde_json::Value = serde_json::from_str(&state::to_json(&emulator.machine, None).unwrap()).unwrap();
+    value["machine"]["memory"] = serde_json::json!([0, 1, 2]);
+    assert!(state::from_json(&value.to_string()).is_err());
+}
+
+#[test]
+fn test_rom_differs() {
+    let exit = run_rom("exit");
+    let bc = run_rom("bc");
+    let json = state::to_json(&exit.machine, Some(&exit.rom_identity)).unwrap();
+    let loaded = state::from_json(&json).unwrap();
+    let rom = |name: &str| Rom::from_file(format!("tests/roms/{}.rom", name)).unwrap();
+    assert!(!loaded.rom_differs(Some(&rom("exit"))));
+    assert!(loaded.rom_differs(Some(&rom("bc"))));
+    assert!(loaded.rom_differs(None));
+    let anonymous = state::from_json(&state::to_json(&bc.machine, None).unwrap()).unwrap();
+    assert!(!anonymous.rom_differs(Some(&rom("exit"))));
+}
@@ -0,0 +1,68 @@
+// these also run under WebAssembly: `cargo test --target wasm32-unknown-unknown --no-default-features --test storage`
+// (with `wasm-bindgen-test-runner` from wasm-bindgen-cli as the target's runner)
+
+use std::path::Path;
+use std::time::Duration;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
+use chipper8::{Emulator, EmulatorConfig, RunOutcome};
+use chipper8::command::{Command, MetaCommand};
+use chipper8::repl::Repl;
+use chipper8::state;
+use chipper8::storage::{MemoryStorage, Storage};
+
+// v0 := 1; v1 := 2; exit
+const PROGRAM: [u8; 6] = [0x60, 0x01, 0x61, 0x02, 0x00, 0xF0];
+
+fn execute(repl: &mut Repl, command: MetaCommand) {
+    repl.execute(&Command::Meta(command)).unwrap();
+}
+
+#[test]
+fn test_memory_storage() {
+    let mut storage = MemoryStorage::new();
+    let path = Path::new("roms/program.ch8");
+    assert!(!storage.exists(path));
+    assert!(storage.read(path).is_err());
+    storage.write(path, &PROGRAM).unwrap();
+    assert!(storage.exists(path));
+    assert_eq!(storage.read(path).unwrap(), PROGRAM);
+}
+
+#[test]
+fn test_repl_uses_storage() {
+    let mut storage = MemoryStorage::new();
+    storage.write(Path::new("roms/program.ch8"), &PROGRAM).unwrap();
+    let mut repl = Repl::new();
+    repl.storage = Box::new(storage);
+    // found by name in the ROM directory, as with files
+    execute(&mut repl, MetaCommand::LoadRom(String::from("program"), None));
+    assert_eq!(repl.machine.memory[0x200..0x206], PROGRAM);
+
+    repl.machine.registers[0xA] = 0x42;
+    execute(&mut repl, MetaCommand::DumpMachine(String::from("saved.c8s")));
+    let saved = state::load_from(repl.storage.as_ref(), Path::new("saved.c8s")).unwrap();
+    assert_eq!(saved.rom.unwrap().name, "program.ch8");
+    repl.machine.registers[0xA] = 0;
+    execute(&mut repl, MetaCommand::LoadMachine(String::from("saved.c8s")));
+    assert_eq!(repl.machine.registers[0xA], 0x42);
+    assert!(repl.state.error.is_none());
+
+    // loads, with a warning, when the state's ROM isn't the loaded one
+    execute(&mut repl, MetaCommand::UnloadRom);
+    execute(&mut repl, MetaCommand::LoadMachine(String::from("saved.c8s")));
+    assert_eq!(repl.machine.registers[0xA], 0x42);
+    assert_eq!(repl.state.error.unwrap().to_string(), "warning: state was saved with a different ROM (program.ch8)");
+}
+
+#[test]
+fn test_run_in_real_time() {
+    // one instruction per frame, paced by the clock rather than sleeping on the web
+    let config = EmulatorConfig { fps: 60, max_duration: Some(Duration::from_secs(1)), ..EmulatorConfig::new() };
+    let mut emulator = Emulator::from_bytes("program", PROGRAM.to_vec(), config);
+    assert!(matches!(emulator.run().unwrap(), RunOutcome::Exited));
+    assert_eq!(emulator.machine.registers[..2], [1, 2]);
+    assert_eq!(emulator.frames, 3);
+}
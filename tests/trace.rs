@@ -0,0 +1,49 @@
+use std::collections::BTreeSet;
+
+use ringbuffer::RingBufferExt;
+
+use chipper8::assembler;
+use chipper8::machine::{Address, Instruction};
+use chipper8::Machine;
+use chipper8::trace::{InstructionKind, TraceFilter, Tracer};
+
+fn machine(source: &str) -> Machine {
+    let mut machine = Machine::new();
+    let start = Address::try_from(0x200u16).unwrap();
+    machine.load(&start, &assembler::compile(source).unwrap().bytes);
+    machine.program_counter = start;
+    machine
+}
+
+#[test]
+fn test_trace_records_changes() {
+    let mut machine = machine("v0 := 123 i := 0x300 bcd v0");
+    let mut tracer = Tracer::new().with_buffer();
+    for _ in 0..3 {
+        tracer.tick(&mut machine).unwrap();
+    }
+    let entries: Vec<_> = tracer.buffer.as_ref().unwrap().iter().cloned().collect();
+    assert_eq!(entries.len(), 3);
+    assert_eq!((entries[0].address, entries[0].opcode), (0x200, 0x607B));
+    assert_eq!(entries[0].registers, vec![(0, 0, 123)]);
+    assert_eq!(entries[1].index, Some((0, 0x300)));
+    assert_eq!(entries[2].memory_writes, vec![(0x300, 1), (0x301, 2), (0x302, 3)]);
+    assert!(entries[2].to_string().contains("[0x300]="));
+}
+
+#[test]
+fn test_trace_filter() {
+    let mut machine = machine("v0 := 1 v1 := 2 i := 0x300 v0 += v1");
+    let filter = TraceFilter {
+        ranges: vec![TraceFilter::parse_range("0x202..0x208").unwrap()],
+        kinds: BTreeSet::from([InstructionKind::Arithmetic]),
+    };
+    let mut tracer = Tracer::new().with_buffer().with_filter(filter);
+    for _ in 0..4 {
+        tracer.tick(&mut machine).unwrap();
+    }
+    let addresses: Vec<_> = tracer.buffer.as_ref().unwrap().iter().map(|entry| entry.address).collect();
+    assert_eq!(addresses, vec![0x202, 0x206]);
+    assert!(matches!(tracer.buffer.as_ref().unwrap().get(0).unwrap().instruction, Instruction::Arithmetic { .. }));
+    assert!(TraceFilter::parse_range("0x300").is_err());
+}